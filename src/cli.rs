@@ -1,7 +1,17 @@
-use clap::{App, Arg, ArgGroup, SubCommand};
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
 
 pub const VERSION: &str = "0.4.0";
 
+const COLUMNS_HELP: &str = "Comma separated columns to export, defaults to\n\
+                            accounting_date,amount,text,transaction_type_text,merchant_name,\n\
+                            merchant_city,merchant_category_code,currency_amount,\n\
+                            original_currency_code.\n\
+                            Also available: interest_date,transaction_type,\n\
+                            transaction_type_code,is_reservation,reservation_type,\n\
+                            other_account_number,transaction_id,card_number,\n\
+                            merchant_category_description,currency_rate,purchase_date.\n\
+                            With --all-accounts, account_name and account_number as well.";
+
 pub fn build_cli() -> App<'static, 'static> {
     App::new("sbanken-cli")
         .version(VERSION)
@@ -19,6 +29,38 @@ pub fn build_cli() -> App<'static, 'static> {
                 .long("color")
                 .help("Allows for colored output. Equal to SBANKEN_COLOR=1."),
         )
+        .arg(
+            Arg::with_name("profile")
+                .short("p")
+                .long("profile")
+                .takes_value(true)
+                .help("Profile from the config file to use. Equal to SBANKEN_PROFILE."),
+        )
+        .arg(
+            Arg::with_name("secret-stdin")
+                .long("secret-stdin")
+                .help("Read the client secret from the first line on stdin"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .possible_values(&["text", "json", "ndjson"])
+                .default_value("text")
+                .help("Output format. ndjson prints lists with one item per line"),
+        )
+        .arg(
+            Arg::with_name("api-url")
+                .long("api-url")
+                .takes_value(true)
+                .help("Base url of the Sbanken APIs. Equal to SBANKEN_API_URL."),
+        )
+        .arg(
+            Arg::with_name("cache-token")
+                .long("cache-token")
+                .help("Cache the access token on disk. Equal to SBANKEN_CACHE_TOKEN=1."),
+        )
         .subcommand(
             SubCommand::with_name("account")
                 .about("See account details")
@@ -32,7 +74,7 @@ pub fn build_cli() -> App<'static, 'static> {
                         .short("a")
                         .long("account")
                         .required(false)
-                        .help("Retrieve details for a specified account")
+                        .help("Retrieve details for an account, given by name, number or alias")
                         .takes_value(true),
                 )
                 .arg(
@@ -49,6 +91,11 @@ pub fn build_cli() -> App<'static, 'static> {
                         .required(false)
                         .help("Retrieve accounts as a list"),
                 )
+                .arg(
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .help("Answer from the accounts stored by sync"),
+                )
                 .display_order(1),
         )
         .subcommand(
@@ -61,18 +108,20 @@ pub fn build_cli() -> App<'static, 'static> {
                 .about("See transactions made on your accounts")
                 .groups(&[
                     ArgGroup::with_name("mode")
-                        .args(&["account", "interactive"])
-                        .required(true),
+                        .args(&["account", "interactive", "all-accounts"])
+                        .required(false),
                     ArgGroup::with_name("optional_args")
-                        .args(&["from", "to", "length"])
-                        .multiple(true)
-                        .requires("mode"),
+                        .args(&["from", "to", "length", "all"])
+                        .multiple(true),
                 ])
                 .arg(
                     Arg::with_name("account")
                         .short("a")
                         .long("account")
-                        .help("List transactions made on your account")
+                        .help(
+                            "List transactions made on an account, given by name, number or alias.\n\
+                             Defaults to default_account of the selected profile.",
+                        )
                         .takes_value(true),
                 )
                 .arg(
@@ -110,6 +159,108 @@ pub fn build_cli() -> App<'static, 'static> {
                         .default_value("20")
                         .takes_value(true),
                 )
+                .arg(
+                    Arg::with_name("all-accounts")
+                        .long("all-accounts")
+                        .help("List transactions across all your accounts, newest first"),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .help("Fetch every transaction in the date range, ignoring --length"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .possible_values(&["csv", "tsv"])
+                        .help("Export transactions as csv or tsv with a header row")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("columns")
+                        .long("columns")
+                        .requires("format")
+                        .help(COLUMNS_HELP)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .help("Answer from the transactions stored by sync"),
+                )
+                .arg(
+                    Arg::with_name("grep")
+                        .long("grep")
+                        .takes_value(true)
+                        .help("Only transactions whose text or merchant matches the regex"),
+                )
+                .arg(
+                    Arg::with_name("min")
+                        .long("min")
+                        .takes_value(true)
+                        .help("Only transactions of at least this amount, in or out"),
+                )
+                .arg(
+                    Arg::with_name("max")
+                        .long("max")
+                        .takes_value(true)
+                        .help("Only transactions of at most this amount, in or out"),
+                )
+                .arg(
+                    Arg::with_name("type")
+                        .long("type")
+                        .takes_value(true)
+                        .help("Only transactions of this type, given by code or text"),
+                )
+                .arg(
+                    Arg::with_name("credits")
+                        .long("credits")
+                        .conflicts_with("debits")
+                        .help("Only money coming in"),
+                )
+                .arg(
+                    Arg::with_name("debits")
+                        .long("debits")
+                        .help("Only money going out"),
+                )
+                .arg(
+                    Arg::with_name("exclude-reservations")
+                        .long("exclude-reservations")
+                        .help("Leave out reservations"),
+                )
+                .arg(
+                    Arg::with_name("card")
+                        .long("card")
+                        .takes_value(true)
+                        .help("Only purchases with the card ending in these digits"),
+                )
+                .arg(
+                    Arg::with_name("reservation-changes")
+                        .long("reservation-changes")
+                        .conflicts_with_all(&["interactive", "format"])
+                        .help("List reservations that have settled or been removed since synced"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(&["date", "amount", "merchant"])
+                        .help("Sort oldest first, largest expense first or by merchant"),
+                )
+                .arg(
+                    Arg::with_name("reverse")
+                        .long("reverse")
+                        .requires("sort")
+                        .help("Turn the sort order around"),
+                )
+                .arg(
+                    Arg::with_name("group-by")
+                        .long("group-by")
+                        .takes_value(true)
+                        .possible_values(&["day", "week", "month", "merchant", "type"])
+                        .conflicts_with_all(&["format", "reservation-changes"])
+                        .help("Group transactions with a subtotal per group and a grand total"),
+                )
                 .display_order(3),
         )
         .subcommand(
@@ -120,10 +271,9 @@ pub fn build_cli() -> App<'static, 'static> {
                         .short("a")
                         .long("amount")
                         .takes_value(true)
-                        .required(true)
-                        .requires_all(&["from", "message", "to"])
-                        .conflicts_with("interactive")
-                        .help("Amount to transfer between accounts"),
+                        .required_unless_one(&["interactive", "batch", "template"])
+                        .conflicts_with_all(&["interactive", "batch"])
+                        .help("Amount to transfer between accounts, overrides the template's"),
                 )
                 .arg(
                     Arg::with_name("from")
@@ -132,8 +282,8 @@ pub fn build_cli() -> App<'static, 'static> {
                         .takes_value(true)
                         .required(true)
                         .requires_all(&["amount", "message", "to"])
-                        .conflicts_with("interactive")
-                        .help("From account you want to withdraw money from"),
+                        .conflicts_with_all(&["interactive", "batch", "template"])
+                        .help("Account to withdraw money from, given by name, number or alias"),
                 )
                 .arg(
                     Arg::with_name("interactive")
@@ -149,7 +299,7 @@ pub fn build_cli() -> App<'static, 'static> {
                         .takes_value(true)
                         .required(true)
                         .requires_all(&["amount", "from", "to"])
-                        .conflicts_with("interactive")
+                        .conflicts_with_all(&["interactive", "batch", "template"])
                         .help("Message to be recorded"),
                 )
                 .arg(
@@ -159,29 +309,225 @@ pub fn build_cli() -> App<'static, 'static> {
                         .takes_value(true)
                         .required(true)
                         .requires_all(&["amount", "from", "message"])
+                        .conflicts_with_all(&["interactive", "batch", "template"])
+                        .help("Account to deposit money into, given by name, number or alias"),
+                )
+                .arg(
+                    Arg::with_name("batch")
+                        .long("batch")
+                        .takes_value(true)
                         .conflicts_with("interactive")
-                        .help("To account you want to deposit money into"),
+                        .help("Make the transfers listed in a csv or json file"),
+                )
+                .arg(
+                    Arg::with_name("template")
+                        .long("template")
+                        .takes_value(true)
+                        .conflicts_with_all(&["interactive", "batch"])
+                        .help("Make the transfer saved as the given template"),
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("Show what would be transferred without sending it"),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .short("y")
+                        .long("yes")
+                        .help("Transfer without asking for confirmation"),
                 )
                 .display_order(4),
         )
+        .subcommand(
+            SubCommand::with_name("template")
+                .about("Manage saved transfers")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("list").about("List the saved templates"))
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Save a transfer as a template")
+                        .arg(
+                            Arg::with_name("name")
+                                .required(true)
+                                .help("Name to run the template by"),
+                        )
+                        .arg(
+                            Arg::with_name("from")
+                                .short("f")
+                                .long("from")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Account to withdraw money from"),
+                        )
+                        .arg(
+                            Arg::with_name("to")
+                                .short("t")
+                                .long("to")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Account to deposit money into"),
+                        )
+                        .arg(
+                            Arg::with_name("amount")
+                                .short("a")
+                                .long("amount")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Default amount to transfer"),
+                        )
+                        .arg(
+                            Arg::with_name("message")
+                                .short("m")
+                                .long("message")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Message to be recorded"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Delete a template")
+                        .arg(Arg::with_name("name").required(true)),
+                )
+                .display_order(5),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about("Store your transactions locally, fetching only what is new")
+                .arg(
+                    Arg::with_name("account")
+                        .short("a")
+                        .long("account")
+                        .takes_value(true)
+                        .help("Only sync the given account, by name, number or alias"),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .short("f")
+                        .long("from")
+                        .takes_value(true)
+                        .help("Start date, yyyy-mm-dd, of the first sync. Defaults to a year ago."),
+                )
+                .display_order(6),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Summarize your finances")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("summary")
+                        .about("Income, expenses and net per calendar month")
+                        .arg(
+                            Arg::with_name("account")
+                                .short("a")
+                                .long("account")
+                                .takes_value(true)
+                                .help(
+                                    "Account to summarize, given by name, number or alias.\n\
+                                     Defaults to default_account of the selected profile.",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("all-accounts")
+                                .long("all-accounts")
+                                .conflicts_with("account")
+                                .help(
+                                    "Summarize all your accounts together, leaving out\n\
+                                     transfers between them",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("from")
+                                .short("f")
+                                .long("from")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Start date, yyyy-mm-dd, of the report"),
+                        )
+                        .arg(
+                            Arg::with_name("to")
+                                .short("t")
+                                .long("to")
+                                .takes_value(true)
+                                .help("End date, yyyy-mm-dd, of the report. Defaults to today."),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .long("format")
+                                .possible_values(&["csv", "tsv"])
+                                .takes_value(true)
+                                .help("Export the months as csv or tsv with a header row"),
+                        ),
+                )
+                .display_order(7),
+        )
+        .subcommand(
+            SubCommand::with_name("tui")
+                .about("Full-screen dashboard of your accounts and transactions")
+                .display_order(8),
+        )
+        .subcommand(
+            SubCommand::with_name("shell")
+                .about("Run commands in one session, with history and tab completion")
+                .display_order(9),
+        )
+        .subcommand(
+            SubCommand::with_name("auth")
+                .about("Check your credentials")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("test")
+                        .about("Request an access token and look up the configured customer"),
+                )
+                .display_order(10),
+        )
         .subcommand(
             SubCommand::with_name("generate-bash-completions")
                 .about("Generate completion script for bash")
-                .display_order(5),
+                .display_order(11),
         )
         .subcommand(
             SubCommand::with_name("generate-zsh-completions")
                 .about("Generate completion script for zsh")
-                .display_order(6),
+                .display_order(12),
         )
         .subcommand(
             SubCommand::with_name("generate-fish-completions")
                 .about("Generate completion script for fish")
-                .display_order(7),
+                .display_order(13),
         )
         .subcommand(
             SubCommand::with_name("generate-powershell-completions")
                 .about("Generate completion script for PowerShell")
-                .display_order(8),
+                .display_order(14),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::export::{COLUMNS, DEFAULT_COLUMNS};
+
+    fn words(text: &str) -> Vec<&str> {
+        text.split(|c: char| c == ',' || c == '.' || c.is_whitespace())
+            .collect()
+    }
+
+    #[test]
+    fn columns_help_lists_every_export_column() {
+        let listed = words(COLUMNS_HELP);
+        for column in COLUMNS {
+            assert!(listed.contains(column), "{} is missing", column);
+        }
+    }
+
+    #[test]
+    fn columns_help_names_the_default_columns() {
+        let end = COLUMNS_HELP.find("Also available").unwrap();
+        let defaults = words(&COLUMNS_HELP[..end]);
+        for column in DEFAULT_COLUMNS.split(',') {
+            assert!(defaults.contains(&column), "{} is missing", column);
+        }
+    }
+}
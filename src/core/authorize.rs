@@ -1,4 +1,5 @@
 use cli::VERSION;
+use core::cache::{CachedToken, TokenCache};
 use core::credentials::Credentials;
 use core::entities::AccessToken;
 use hyper::header::{Accept, Authorization, Basic, Bearer, ContentType, Headers, UserAgent};
@@ -6,24 +7,46 @@ use reqwest::{Client, Error, Response};
 use serde::Serialize;
 use serde_json;
 use std::collections::HashMap;
+use std::sync::Mutex;
+use url;
 use url::form_urlencoded::byte_serialize;
 use url::Url;
 
 pub struct Authorize<'a> {
     pub credentials: &'a Credentials,
+    pub api_url: Url,
+    token: Mutex<Option<CachedToken>>,
+    token_cache: Option<TokenCache>,
 }
 
 impl<'a> Authorize<'a> {
-    const IDENTITY_SERVER_URL: &'static str = "https://api.sbanken.no/identityserver/connect/token";
+    const IDENTITY_SERVER_PATH: &'static str = "identityserver/connect/token";
+
+    pub fn new(
+        credentials: &'a Credentials,
+        api_url: Url,
+        token_cache: Option<TokenCache>,
+    ) -> Authorize {
+        Authorize {
+            credentials,
+            api_url,
+            token: Mutex::new(None),
+            token_cache,
+        }
+    }
 
-    pub fn new(credentials: &'a Credentials) -> Authorize {
-        Authorize { credentials }
+    /// Resolves an API path, e.g. `bank/api/v1/accounts`, against the
+    /// configured base url.
+    pub fn endpoint(&self, path: &str) -> Result<Url, url::ParseError> {
+        self.api_url.join(path)
     }
 
-    fn get_access_token(&self) -> Result<AccessToken, Error> {
+    /// Requests a new access token from the identity server, bypassing any
+    /// cached token.
+    pub fn get_access_token(&self) -> Result<AccessToken, Error> {
         let username: String = byte_serialize(self.credentials.client_id.as_bytes()).collect();
         let password: Option<String> =
-            Some(byte_serialize(self.credentials.secret.as_bytes()).collect());
+            Some(byte_serialize(self.credentials.secret.expose().as_bytes()).collect());
 
         let mut headers = Headers::new();
         headers.set(UserAgent::new(format!("sbanken-cli/{}", VERSION)));
@@ -31,15 +54,58 @@ impl<'a> Authorize<'a> {
         headers.set(ContentType::form_url_encoded());
         headers.set(Authorization(Basic { username, password }));
 
+        // A path that doesn't join is handed on as it is, so reqwest reports
+        // it as a url error.
+        let url: String = match self.endpoint(Authorize::IDENTITY_SERVER_PATH) {
+            Ok(url) => url.into_string(),
+            Err(_) => Authorize::IDENTITY_SERVER_PATH.to_string(),
+        };
+
         let client = Client::builder().default_headers(headers).build()?;
         let mut response = client
-            .post(Authorize::IDENTITY_SERVER_URL)
+            .post(&url)
             .body("grant_type=client_credentials")
-            .send()?;
+            .send()?
+            .error_for_status()?;
 
         response.json::<AccessToken>()
     }
 
+    /// Returns a bearer token, reusing the one held in memory or in the
+    /// on-disk cache as long as it has not expired.
+    fn bearer_token(&self) -> Result<String, Error> {
+        let mut token = self.token.lock().unwrap();
+
+        if let Some(ref cached) = *token {
+            if cached.is_valid() {
+                return Ok(cached.access_token.to_string());
+            }
+        }
+
+        let client_id: &str = &self.credentials.client_id;
+
+        if let Some(ref cache) = self.token_cache {
+            if let Some(cached) = cache.load(client_id, self.api_url.as_str()) {
+                let access_token = cached.access_token.to_string();
+                *token = Some(cached);
+                return Ok(access_token);
+            }
+        }
+
+        let cached = CachedToken::new(client_id, self.api_url.as_str(), self.get_access_token()?);
+
+        if let Some(ref cache) = self.token_cache {
+            // The cache is only an optimization, a failed write must not
+            // fail the request.
+            let _ = cache.store(&cached);
+        }
+
+        let access_token = cached.access_token.to_string();
+        *token = Some(cached);
+
+        Ok(access_token)
+    }
+
     fn construct_headers(&self, token: String) -> Headers {
         let mut headers = Headers::new();
         headers.set(UserAgent::new(format!("sbanken-cli/{}", VERSION)));
@@ -55,9 +121,9 @@ impl<'a> Authorize<'a> {
         url: Url,
         params: Option<HashMap<&str, String>>,
     ) -> Result<Response, Error> {
-        let token: AccessToken = self.get_access_token()?;
+        let token: String = self.bearer_token()?;
 
-        let headers: Headers = self.construct_headers(token.access_token);
+        let headers: Headers = self.construct_headers(token);
 
         let client = Client::builder().default_headers(headers).build()?;
 
@@ -69,8 +135,8 @@ impl<'a> Authorize<'a> {
     }
 
     pub fn post_request(&self, url: Url, object: impl Serialize) -> Result<Response, Error> {
-        let token: AccessToken = self.get_access_token()?;
-        let headers = self.construct_headers(token.access_token);
+        let token: String = self.bearer_token()?;
+        let headers = self.construct_headers(token);
 
         let client = Client::builder().default_headers(headers).build()?;
 
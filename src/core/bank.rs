@@ -1,9 +1,18 @@
 use chrono::{DateTime, SecondsFormat, Utc};
 use core::authorize::Authorize;
-use core::entities::{Account, Accounts, Transactions, TransferRequest, TransferResponse};
-use reqwest::{Error, Response};
+use core::entities::{
+    Account, AccountObj, AccountTransactionObj, AccountTransactions, Accounts, MultipleItems,
+    Transactions, TransferRequest, TransferResponse,
+};
+use core::error::Error;
+use core::response::read_json;
+use reqwest::Response;
 use std::collections::HashMap;
-use url::Url;
+use std::thread;
+use url::percent_encoding::{utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+
+// The largest page the transactions endpoint hands out.
+const PAGE_LENGTH: i32 = 1000;
 
 pub struct BankAPI<'a> {
     authorize: &'a Authorize<'a>,
@@ -15,23 +24,21 @@ impl<'a> BankAPI<'a> {
     }
 
     pub fn get_accounts(&self) -> Result<Accounts, Error> {
-        let url = Url::parse("https://api.sbanken.no/bank/api/v1/accounts").unwrap();
+        let url = self.authorize.endpoint("bank/api/v1/accounts")?;
 
-        let mut response: Response = self.authorize.get_request(url, None)?;
+        let response: Response = self.authorize.get_request(url, None)?;
 
-        response.json::<Accounts>()
+        read_json::<Accounts>(response)
     }
 
     pub fn get_account(&self, account: &str) -> Result<Account, Error> {
-        let url = Url::parse(&format!(
-            "https://api.sbanken.no/bank/api/v1/accounts/{}/",
-            account
-        ))
-        .unwrap();
+        let url = self
+            .authorize
+            .endpoint(&format!("bank/api/v1/accounts/{}/", segment(account)))?;
 
-        let mut response: Response = self.authorize.get_request(url, None)?;
+        let response: Response = self.authorize.get_request(url, None)?;
 
-        response.json::<Account>()
+        read_json::<Account>(response)
     }
 
     pub fn get_transactions(
@@ -41,13 +48,134 @@ impl<'a> BankAPI<'a> {
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
     ) -> Result<Transactions, Error> {
-        let url = Url::parse(&format!(
-            "https://api.sbanken.no/bank/api/v1/transactions/{}",
-            account
-        ))
-        .unwrap();
+        self.get_transactions_page(account, 0, length, start_date, end_date)
+    }
+
+    /// Fetches every transaction in the date range, page by page, until
+    /// `available_items` have been collected. `progress` is called after
+    /// each page with the number of items fetched so far and the total.
+    pub fn get_all_transactions<F>(
+        &self,
+        account: &str,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        mut progress: F,
+    ) -> Result<Transactions, Error>
+    where
+        F: FnMut(usize, i32),
+    {
+        let mut transactions =
+            self.get_transactions_page(account, 0, PAGE_LENGTH, start_date, end_date)?;
+
+        progress(transactions.items.len(), transactions.available_items);
+
+        while (transactions.items.len() as i32) < transactions.available_items {
+            let index = transactions.items.len() as i32;
+            let mut page =
+                self.get_transactions_page(account, index, PAGE_LENGTH, start_date, end_date)?;
+
+            if page.items.is_empty() {
+                break;
+            }
+
+            transactions.available_items = page.available_items;
+            transactions.items.append(&mut page.items);
+
+            progress(transactions.items.len(), transactions.available_items);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Fetches transactions for every account in parallel and merges them,
+    /// newest first. With `length` set to `None` every page is fetched, and
+    /// `progress` is called as for `get_all_transactions`.
+    pub fn get_transactions_for_accounts<F>(
+        &self,
+        accounts: &[AccountObj],
+        length: Option<i32>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        progress: F,
+    ) -> Result<AccountTransactions, Error>
+    where
+        F: Fn(&AccountObj, usize, i32) + Sync,
+    {
+        let progress = &progress;
+
+        let results: Vec<Result<Transactions, Error>> = thread::scope(|scope| {
+            let handles: Vec<_> = accounts
+                .iter()
+                .map(|account| {
+                    scope.spawn(move || match length {
+                        Some(length) => {
+                            self.get_transactions(&account.account_id, length, start_date, end_date)
+                        }
+                        None => self.get_all_transactions(
+                            &account.account_id,
+                            start_date,
+                            end_date,
+                            |fetched, available| progress(account, fetched, available),
+                        ),
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        let mut available_items: i32 = 0;
+        let mut items: Vec<AccountTransactionObj> = Vec::new();
+
+        for (account, result) in accounts.iter().zip(results) {
+            let transactions = result?;
+
+            available_items += transactions.available_items;
+            items.extend(
+                transactions
+                    .items
+                    .into_iter()
+                    .map(|transaction| AccountTransactionObj {
+                        account_name: account.name.to_string(),
+                        account_number: account.account_number.to_string(),
+                        transaction,
+                    }),
+            );
+        }
+
+        items.sort_by(|a, b| {
+            b.transaction
+                .accounting_date
+                .cmp(&a.transaction.accounting_date)
+        });
+
+        Ok(MultipleItems {
+            available_items,
+            items,
+            error_type: None,
+            is_error: false,
+            error_message: None,
+            trace_id: None,
+        })
+    }
+
+    fn get_transactions_page(
+        &self,
+        account: &str,
+        index: i32,
+        length: i32,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Transactions, Error> {
+        let url = self
+            .authorize
+            .endpoint(&format!("bank/api/v1/transactions/{}", segment(account)))?;
 
         let mut params: HashMap<&str, String> = HashMap::new();
+        params.insert("index", index.to_string());
         params.insert("length", length.to_string());
         params.insert(
             "startDate",
@@ -58,16 +186,49 @@ impl<'a> BankAPI<'a> {
             end_date.to_rfc3339_opts(SecondsFormat::Secs, true),
         );
 
-        let mut response: Response = self.authorize.get_request(url, Some(params))?;
+        let response: Response = self.authorize.get_request(url, Some(params))?;
 
-        response.json::<Transactions>()
+        read_json::<Transactions>(response)
     }
 
     pub fn post_transfer(&self, transfer: &TransferRequest) -> Result<TransferResponse, Error> {
-        let url = Url::parse("https://api.sbanken.no/bank/api/v1/transfers").unwrap();
+        let url = self.authorize.endpoint("bank/api/v1/transfers")?;
+
+        let response: Response = self.authorize.post_request(url, transfer)?;
+
+        match read_json::<TransferResponse>(response) {
+            Err(Error::Api {
+                error_type,
+                message,
+                trace_id,
+                ..
+            }) => Err(Error::TransferRejected {
+                error_type,
+                message,
+                trace_id,
+            }),
+            result => result,
+        }
+    }
+}
 
-        let mut response: Response = self.authorize.post_request(url, transfer)?;
+// Encodes an account id for use as one segment of a path, so an id holding
+// `/`, `?` or `#` can't change which endpoint is requested.
+fn segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT_ENCODE_SET).to_string()
+}
 
-        response.json::<TransferResponse>()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_account_ids_as_one_path_segment() {
+        assert_eq!(
+            segment("00000000000000000000000000000001"),
+            "00000000000000000000000000000001"
+        );
+        assert_eq!(segment("a/../b"), "a%2F..%2Fb");
+        assert_eq!(segment("a?b#c"), "a%3Fb%23c");
     }
 }
@@ -0,0 +1,336 @@
+use core::bank::BankAPI;
+use core::entities::{AccountObj, TransferRequest};
+use core::error::Error;
+use core::resolve::resolve_account;
+use core::transfer::{validate, TransferSummary};
+use csv::{ReaderBuilder, Trim};
+use rust_decimal::Decimal;
+use serde_json;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+/// One transfer as given in a batch file. `from` and `to` take anything
+/// `resolve_account` does.
+#[derive(Debug, Deserialize)]
+pub struct BatchRow {
+    pub from: String,
+    pub to: String,
+    pub amount: Decimal,
+    pub message: String,
+}
+
+/// A row that has been resolved and validated, ready to be sent.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedTransfer {
+    pub row: usize,
+    #[serde(skip)]
+    pub request: TransferRequest,
+    #[serde(flatten)]
+    pub summary: TransferSummary,
+}
+
+impl fmt::Display for PreparedTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Row:\t\t{}", self.row)?;
+        write!(f, "{}", self.summary)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    pub row: usize,
+    pub from_account_name: String,
+    pub to_account_name: String,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub amount: Decimal,
+    pub message: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub trace_id: Option<String>,
+}
+
+impl fmt::Display for BatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Row {}:\t{} {} -> {} \"{}\"",
+            self.row, self.amount, self.from_account_name, self.to_account_name, self.message
+        )?;
+
+        match self.error {
+            Some(ref error) => write!(f, "\tfailed: {}", error),
+            None => write!(f, "\tok"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub rows: Vec<BatchResult>,
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.rows {
+            writeln!(f, "{}", row)?;
+        }
+        write!(
+            f,
+            "{} transfers succeeded, {} failed",
+            self.succeeded, self.failed
+        )
+    }
+}
+
+/// Reads the rows of a batch file. Files ending in `.json` hold an array
+/// of objects, anything else is read as CSV with the header
+/// `from,to,amount,message`.
+pub fn read_rows(path: &str) -> Result<Vec<BatchRow>, Error> {
+    let is_json = Path::new(path)
+        .extension()
+        .map_or(false, |extension| extension == "json");
+
+    if is_json {
+        return Ok(serde_json::from_reader(File::open(path)?)?);
+    }
+
+    let mut reader = ReaderBuilder::new().trim(Trim::All).from_path(path)?;
+
+    let mut rows: Vec<BatchRow> = Vec::new();
+    for row in reader.deserialize() {
+        rows.push(row?);
+    }
+
+    Ok(rows)
+}
+
+/// Resolves the accounts of every row and validates it, with the balances
+/// the accounts will have once the rows before it are made. Returns the
+/// result of every row, so all invalid rows can be reported at once.
+pub fn prepare(
+    rows: &[BatchRow],
+    accounts: &[AccountObj],
+    aliases: &HashMap<String, String>,
+) -> Vec<Result<PreparedTransfer, Error>> {
+    let mut balances: Vec<AccountObj> = accounts.to_vec();
+
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| -> Result<PreparedTransfer, Error> {
+            let request = TransferRequest {
+                from_account_id: resolve_account(accounts, aliases, &row.from)?
+                    .account_id
+                    .to_string(),
+                to_account_id: resolve_account(accounts, aliases, &row.to)?
+                    .account_id
+                    .to_string(),
+                message: row.message.to_string(),
+                amount: row.amount,
+            };
+
+            validate(&balances, &request)?;
+            let summary = TransferSummary::new(&balances, &request)?;
+
+            for account in &mut balances {
+                if account.account_id == request.from_account_id {
+                    account.available -= request.amount;
+                } else if account.account_id == request.to_account_id {
+                    account.available += request.amount;
+                }
+            }
+
+            Ok(PreparedTransfer {
+                row: i + 1,
+                request,
+                summary,
+            })
+        })
+        .collect()
+}
+
+/// Sends the transfers one at a time. A failed transfer is recorded in the
+/// report and doesn't stop the ones after it.
+pub fn execute(bank_api: &BankAPI, transfers: Vec<PreparedTransfer>) -> BatchReport {
+    let mut report = BatchReport {
+        succeeded: 0,
+        failed: 0,
+        rows: Vec::new(),
+    };
+
+    for transfer in transfers {
+        let (error, trace_id) = match bank_api.post_transfer(&transfer.request) {
+            Ok(_) => {
+                report.succeeded += 1;
+                (None, None)
+            }
+            Err(err) => {
+                report.failed += 1;
+                (
+                    Some(err.to_string()),
+                    err.trace_id().map(|trace_id| trace_id.to_string()),
+                )
+            }
+        };
+
+        report.rows.push(BatchResult {
+            row: transfer.row,
+            from_account_name: transfer.summary.from_account_name,
+            to_account_name: transfer.summary.to_account_name,
+            amount: transfer.summary.amount,
+            message: transfer.summary.message,
+            success: error.is_none(),
+            error,
+            trace_id,
+        });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::error::EXIT_INVALID_TRANSFER;
+    use core::transfer::ValidationError;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::process;
+
+    fn account(account_id: &str, name: &str, available: Decimal) -> AccountObj {
+        AccountObj {
+            account_id: account_id.to_string(),
+            account_number: format!("9710{}", account_id),
+            owner_customer_id: "12345678901".to_string(),
+            name: name.to_string(),
+            account_type: "Standard account".to_string(),
+            available,
+            balance: available,
+            credit_limit: Decimal::new(0, 0),
+        }
+    }
+
+    fn accounts() -> Vec<AccountObj> {
+        vec![
+            account("1", "Brukskonto", Decimal::new(1000, 0)),
+            account("2", "Sparekonto", Decimal::new(0, 0)),
+        ]
+    }
+
+    fn row(from: &str, to: &str, amount: Decimal, message: &str) -> BatchRow {
+        BatchRow {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            message: message.to_string(),
+        }
+    }
+
+    fn write_file(name: &str, contents: &str) -> String {
+        let path = env::temp_dir().join(format!("sbanken-batch-{}-{}", process::id(), name));
+        File::create(&path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn resolves_accounts_by_name_and_alias() {
+        let mut aliases = HashMap::new();
+        aliases.insert("savings".to_string(), "2".to_string());
+
+        let rows = vec![row("brukskonto", "savings", Decimal::new(100, 0), "Buffer")];
+        let prepared = prepare(&rows, &accounts(), &aliases);
+
+        let transfer = prepared[0].as_ref().unwrap();
+        assert_eq!(transfer.row, 1);
+        assert_eq!(transfer.request.from_account_id, "1");
+        assert_eq!(transfer.request.to_account_id, "2");
+    }
+
+    #[test]
+    fn validates_each_row_against_the_balances_left_by_the_rows_before() {
+        let rows = vec![
+            row("1", "2", Decimal::new(600, 0), "First"),
+            row("1", "2", Decimal::new(600, 0), "Second"),
+            row("2", "1", Decimal::new(600, 0), "Back"),
+        ];
+        let prepared = prepare(&rows, &accounts(), &HashMap::new());
+
+        assert!(prepared[0].is_ok());
+        match prepared[1] {
+            Err(Error::InvalidTransfer(ValidationError::InsufficientFunds { .. })) => {}
+            ref other => panic!("expected insufficient funds, got {:?}", other),
+        }
+        assert!(prepared[2].is_ok());
+    }
+
+    #[test]
+    fn reports_every_invalid_row() {
+        let rows = vec![
+            row("1", "Lønnskonto", Decimal::new(10, 0), "Unknown"),
+            row("1", "2", Decimal::new(10, 0), "Fine"),
+            row("1", "2", Decimal::new(-10, 0), "Negative"),
+        ];
+        let prepared = prepare(&rows, &accounts(), &HashMap::new());
+
+        assert_eq!(prepared.len(), 3);
+        match prepared[0] {
+            Err(Error::UnknownAccount(ref input)) => assert_eq!(input, "Lønnskonto"),
+            ref other => panic!("expected an unknown account, got {:?}", other),
+        }
+        assert_eq!(prepared[1].as_ref().unwrap().row, 2);
+        match prepared[2] {
+            Err(Error::InvalidTransfer(ValidationError::AmountNotPositive)) => {}
+            ref other => panic!("expected a non-positive amount, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_csv_rows_with_surrounding_whitespace() {
+        let path = write_file(
+            "rows.csv",
+            "from, to, amount, message\n1, 2, 1.50, Rent May \nbrukskonto,savings,20,Buffer\n",
+        );
+        let rows = read_rows(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].from, "1");
+        assert_eq!(rows[0].amount, Decimal::new(150, 2));
+        assert_eq!(rows[0].message, "Rent May");
+        assert_eq!(rows[1].to, "savings");
+    }
+
+    #[test]
+    fn reads_json_rows() {
+        let path = write_file(
+            "rows.json",
+            r#"[{"from": "1", "to": "2", "amount": 12.34, "message": "Rent"}]"#,
+        );
+        let rows = read_rows(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].amount, Decimal::new(1234, 2));
+    }
+
+    #[test]
+    fn invalid_rows_exit_as_an_invalid_transfer() {
+        let err = Error::InvalidTransfer(ValidationError::InvalidBatchRows(2));
+
+        assert_eq!(err.exit_code(), EXIT_INVALID_TRANSFER);
+        assert_eq!(
+            err.to_string(),
+            "Invalid transfer: 2 rows of the batch are invalid, no transfers were made"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use core::entities::AccessToken;
+use dirs;
+use serde_json;
+use std::fs::{DirBuilder, File, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use time::Duration;
+
+#[cfg(unix)]
+use std::fs::{set_permissions, Permissions};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+
+// Tokens this close to expiry are treated as expired, so a request never
+// goes out with a token that runs out while in flight.
+const EXPIRY_MARGIN_SECONDS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedToken {
+    pub client_id: String,
+    pub api_url: String,
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl CachedToken {
+    pub fn new(client_id: &str, api_url: &str, token: AccessToken) -> CachedToken {
+        CachedToken {
+            client_id: client_id.to_string(),
+            api_url: api_url.to_string(),
+            access_token: token.access_token,
+            token_type: token.token_type,
+            expires_at: Utc::now() + Duration::seconds(i64::from(token.expires_in)),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        Utc::now() + Duration::seconds(EXPIRY_MARGIN_SECONDS) < self.expires_at
+    }
+}
+
+pub struct TokenCache {
+    path: PathBuf,
+}
+
+impl TokenCache {
+    pub fn new() -> Option<TokenCache> {
+        dirs::cache_dir().map(|dir| TokenCache {
+            path: dir.join("sbanken-cli").join("token.json"),
+        })
+    }
+
+    pub fn load(&self, client_id: &str, api_url: &str) -> Option<CachedToken> {
+        let file = File::open(&self.path).ok()?;
+        let token: CachedToken = serde_json::from_reader(file).ok()?;
+
+        if token.client_id == client_id && token.api_url == api_url && token.is_valid() {
+            Some(token)
+        } else {
+            None
+        }
+    }
+
+    pub fn store(&self, token: &CachedToken) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            let mut builder = DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            builder.mode(0o700);
+            builder.create(dir)?;
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let file = options.open(&self.path)?;
+
+        // `mode` only applies when the file is created, so tighten
+        // permissions on a cache file that already existed as well.
+        #[cfg(unix)]
+        set_permissions(&self.path, Permissions::from_mode(0o600))?;
+
+        serde_json::to_writer(file, token).map_err(io::Error::from)
+    }
+}
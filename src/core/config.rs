@@ -0,0 +1,128 @@
+use core::error::Error;
+use dirs;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use toml;
+use url::Url;
+
+pub const DEFAULT_API_URL: &str = "https://api.sbanken.no/";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    pub api_url: Option<String>,
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    /// Names for accounts, mapping to an account name, number or id.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Fuzzy-matcher for interactive mode: fzf, skim or builtin.
+    pub picker: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Profile {
+    pub client_id: Option<String>,
+    pub customer_id: Option<String>,
+    /// The client secret itself. Prefer any of the other secret sources
+    /// over storing the secret in plain text.
+    pub secret: Option<String>,
+    /// Name of an environment variable holding the client secret.
+    pub secret_env: Option<String>,
+    /// Shell command printing the client secret, e.g. `pass show sbanken`.
+    pub secret_command: Option<String>,
+    /// File holding the client secret. Must have mode 0600.
+    pub secret_file: Option<String>,
+    pub default_account: Option<String>,
+    pub color: Option<bool>,
+    pub api_url: Option<String>,
+    /// Aliases for this profile, taking precedence over the global ones.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("sbanken-cli").join("config.toml"))
+    }
+
+    /// Loads the config file, falling back to an empty config when no
+    /// file has been written yet.
+    pub fn load() -> Result<Config, Error> {
+        let path = match Config::path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let mut content = String::new();
+        File::open(&path)?.read_to_string(&mut content)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Looks up the profile to use. An explicitly given name must exist,
+    /// otherwise `default_profile` is used when set.
+    pub fn profile<'a>(&'a self, name: Option<&'a str>) -> Result<Option<NamedProfile<'a>>, Error> {
+        let name: &str = match name {
+            Some(name) => name,
+            None => match self.default_profile {
+                Some(ref name) => name,
+                None => return Ok(None),
+            },
+        };
+
+        match self.profiles.get(name) {
+            Some(profile) => Ok(Some(NamedProfile { name, profile })),
+            None => Err(Error::Config("profile not found in config file")),
+        }
+    }
+
+    /// Merges the global aliases with the ones of the given profile.
+    pub fn aliases(&self, profile: Option<&NamedProfile>) -> HashMap<String, String> {
+        let mut aliases = self.aliases.clone();
+
+        if let Some(profile) = profile {
+            for (alias, account) in &profile.profile.aliases {
+                aliases.insert(alias.to_string(), account.to_string());
+            }
+        }
+
+        aliases
+    }
+}
+
+pub struct NamedProfile<'a> {
+    pub name: &'a str,
+    pub profile: &'a Profile,
+}
+
+/// Parses the base URL all API endpoints are resolved against.
+///
+/// A trailing slash is added when missing, so `http://localhost:8080/mock`
+/// resolves `bank/api/v1/accounts` below `/mock` rather than replacing it.
+pub fn parse_api_url(api_url: &str) -> Result<Url, Error> {
+    let mut url = Url::parse(api_url)?;
+
+    // `localhost:8080` parses with `localhost` as the scheme, so anything
+    // but http and https is refused here rather than failing on request.
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(Error::Config("api url must use http or https"));
+    }
+
+    if url.cannot_be_a_base() {
+        return Err(Error::Config("api url must be an absolute http(s) url"));
+    }
+
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+
+    Ok(url)
+}
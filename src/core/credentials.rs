@@ -1,15 +1,203 @@
+use core::config::NamedProfile;
+use core::error::Error;
+use core::secret::Secret;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use zeroize::Zeroize;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// Where a credential value was read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Env(String),
+    Profile(String),
+    Command(String),
+    File(String),
+    Stdin,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Env(ref name) => write!(f, "env {}", name),
+            Source::Profile(ref name) => write!(f, "profile {}", name),
+            Source::Command(ref command) => write!(f, "command `{}`", command),
+            Source::File(ref path) => write!(f, "file {}", path),
+            Source::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+pub struct CredentialSources {
+    pub secret: Source,
+    pub client_id: Source,
+    pub customer_id: Source,
+}
+
 pub struct Credentials {
-    pub secret: String,
+    pub secret: Secret,
     pub client_id: String,
     pub customer_id: String,
+    pub sources: CredentialSources,
 }
 
 impl Credentials {
-    pub fn new(secret: String, client_id: String, customer_id: String) -> Credentials {
+    pub fn new(
+        secret: Secret,
+        client_id: String,
+        customer_id: String,
+        sources: CredentialSources,
+    ) -> Credentials {
         Credentials {
             secret,
             client_id,
             customer_id,
+            sources,
+        }
+    }
+
+    /// Reads the credentials from the environment, falling back to the
+    /// given profile for every value the environment doesn't set. With
+    /// `secret_stdin` the secret is read from the first line on stdin.
+    pub fn load(profile: Option<&NamedProfile>, secret_stdin: bool) -> Result<Credentials, Error> {
+        let (secret, secret_source) = load_secret(profile, secret_stdin)?;
+
+        if secret.is_empty() {
+            return Err(Error::Config("the client secret is empty"));
+        }
+
+        let (client_id, client_id_source) = lookup(
+            "SBANKEN_CLIENT_ID",
+            profile.and_then(|p| p.profile.client_id.as_ref().map(|v| (p.name, v))),
+        )?;
+
+        let (customer_id, customer_id_source) = lookup(
+            "SBANKEN_CUSTOMER_ID",
+            profile.and_then(|p| p.profile.customer_id.as_ref().map(|v| (p.name, v))),
+        )?;
+
+        Ok(Credentials::new(
+            secret,
+            client_id,
+            customer_id,
+            CredentialSources {
+                secret: secret_source,
+                client_id: client_id_source,
+                customer_id: customer_id_source,
+            },
+        ))
+    }
+}
+
+fn lookup(
+    env_name: &'static str,
+    profile_value: Option<(&str, &String)>,
+) -> Result<(String, Source), Error> {
+    if let Ok(value) = env::var(env_name) {
+        return Ok((value, Source::Env(env_name.to_string())));
+    }
+
+    match profile_value {
+        Some((profile, value)) => Ok((value.to_string(), Source::Profile(profile.to_string()))),
+        None => Err(Error::CredentialMissing(env_name)),
+    }
+}
+
+fn load_secret(
+    profile: Option<&NamedProfile>,
+    secret_stdin: bool,
+) -> Result<(Secret, Source), Error> {
+    if secret_stdin {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        return Ok((Secret::from_first_line(line), Source::Stdin));
+    }
+
+    if let Ok(secret) = env::var("SBANKEN_SECRET") {
+        return Ok((
+            Secret::new(secret),
+            Source::Env("SBANKEN_SECRET".to_string()),
+        ));
+    }
+
+    if let Some(profile) = profile {
+        if let Some(ref command) = profile.profile.secret_command {
+            let secret = run_secret_command(command)?;
+            return Ok((secret, Source::Command(command.to_string())));
+        }
+
+        if let Some(ref path) = profile.profile.secret_file {
+            let secret = read_secret_file(path)?;
+            return Ok((secret, Source::File(path.to_string())));
+        }
+
+        if let Some(ref secret_env) = profile.profile.secret_env {
+            return match env::var(secret_env) {
+                Ok(secret) => Ok((Secret::new(secret), Source::Env(secret_env.to_string()))),
+                Err(_) => Err(Error::Config(
+                    "env given as secret_env in profile is missing",
+                )),
+            };
         }
     }
+
+    let (secret, source) = lookup(
+        "SBANKEN_SECRET",
+        profile.and_then(|p| p.profile.secret.as_ref().map(|v| (p.name, v))),
+    )?;
+
+    Ok((Secret::new(secret), source))
+}
+
+/// Runs `command` through the shell, e.g. `pass show sbanken`, and takes the
+/// first line it prints as the secret.
+fn run_secret_command(command: &str) -> Result<Secret, Error> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        let mut stdout = output.stdout;
+        stdout.zeroize();
+        return Err(Error::Config("secret_command exited with an error"));
+    }
+
+    match String::from_utf8(output.stdout) {
+        Ok(stdout) => Ok(Secret::from_first_line(stdout)),
+        Err(err) => {
+            err.into_bytes().zeroize();
+            Err(Error::Config("secret_command printed invalid utf-8"))
+        }
+    }
+}
+
+/// Reads the first line of `path`, refusing files that others than the
+/// owner can read or write.
+fn read_secret_file(path: &str) -> Result<Secret, Error> {
+    let mut file = File::open(path)?;
+
+    #[cfg(unix)]
+    {
+        if file.metadata()?.permissions().mode() & 0o077 != 0 {
+            return Err(Error::Config(
+                "secret_file must only be accessible by its owner, run `chmod 600` on it",
+            ));
+        }
+    }
+
+    let mut content = String::new();
+    if let Err(err) = file.read_to_string(&mut content) {
+        content.zeroize();
+        return Err(Error::Io(err));
+    }
+
+    Ok(Secret::from_first_line(content))
 }
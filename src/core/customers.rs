@@ -1,7 +1,8 @@
 use core::authorize::Authorize;
 use core::entities::Customer;
-use reqwest::{Error, Response};
-use url::Url;
+use core::error::Error;
+use core::response::read_json;
+use reqwest::Response;
 
 pub struct CustomersAPI<'a> {
     authorize: &'a Authorize<'a>,
@@ -13,10 +14,10 @@ impl<'a> CustomersAPI<'a> {
     }
 
     pub fn get_customer(&self) -> Result<Customer, Error> {
-        let url = Url::parse("https://api.sbanken.no/customers/api/v1/customers").unwrap();
+        let url = self.authorize.endpoint("customers/api/v1/customers")?;
 
-        let mut response: Response = self.authorize.get_request(url, None)?;
+        let response: Response = self.authorize.get_request(url, None)?;
 
-        response.json::<Customer>()
+        read_json::<Customer>(response)
     }
 }
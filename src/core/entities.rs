@@ -1,6 +1,7 @@
+use rust_decimal::Decimal;
 use std::fmt;
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AccountObj {
     pub account_id: String,
@@ -8,9 +9,12 @@ pub struct AccountObj {
     pub owner_customer_id: String,
     pub name: String,
     pub account_type: String,
-    pub available: f32,
-    pub balance: f32,
-    pub credit_limit: f32,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub available: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub balance: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub credit_limit: Decimal,
 }
 
 impl fmt::Display for AccountObj {
@@ -34,7 +38,7 @@ impl fmt::Display for AccountObj {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MultipleItems<T> {
     pub available_items: i32,
@@ -54,7 +58,7 @@ impl<T: fmt::Display> fmt::Display for MultipleItems<T> {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SingleItem<T> {
     pub item: T,
@@ -70,10 +74,22 @@ impl<T: fmt::Display> fmt::Display for SingleItem<T> {
     }
 }
 
+/// The error fields every API response carries, used to check a response
+/// for errors before parsing the rest of it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorObj {
+    pub error_type: Option<String>,
+    #[serde(default)]
+    pub is_error: bool,
+    pub error_message: Option<String>,
+    pub trace_id: Option<String>,
+}
+
 pub type Account = SingleItem<AccountObj>;
 pub type Accounts = MultipleItems<AccountObj>;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AddressObj {
     pub address_line1: Option<String>,
@@ -85,7 +101,7 @@ pub struct AddressObj {
     pub city: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PhoneNumberObj {
     pub country_code: String,
@@ -98,7 +114,7 @@ impl fmt::Display for PhoneNumberObj {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomerObj {
     pub customer_id: String,
@@ -138,13 +154,15 @@ pub struct AccessToken {
     pub access_token: String,
     pub expires_in: i32,
     pub token_type: String,
+    pub scope: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CardDetailsObj {
     pub card_number: String,
-    pub currency_amount: f32,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub currency_amount: Decimal,
     pub currency_rate: f32,
     pub merchant_category_code: String,
     pub merchant_category_description: String,
@@ -155,14 +173,15 @@ pub struct CardDetailsObj {
     pub transaction_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionObj {
     pub accounting_date: String,
     pub interest_date: String,
     pub other_account_number_specified: bool,
     pub other_account_number: Option<String>,
-    pub amount: f32,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub amount: Decimal,
     pub text: String,
     pub transaction_type: String,
     pub transaction_type_code: i32,
@@ -193,21 +212,155 @@ impl fmt::Display for TransactionObj {
     }
 }
 
+impl AsRef<TransactionObj> for TransactionObj {
+    fn as_ref(&self) -> &TransactionObj {
+        self
+    }
+}
+
+#[cfg(test)]
+impl TransactionObj {
+    /// A booked purchase without card details, for tests.
+    pub fn fixture(date: &str, amount: Decimal, text: &str) -> TransactionObj {
+        TransactionObj {
+            accounting_date: format!("{}T00:00:00", date),
+            interest_date: format!("{}T00:00:00", date),
+            other_account_number_specified: false,
+            other_account_number: None,
+            amount,
+            text: text.to_string(),
+            transaction_type: "VAREKJØP".to_string(),
+            transaction_type_code: 714,
+            transaction_type_text: "Varekjøp".to_string(),
+            is_reservation: false,
+            reservation_type: None,
+            card_details_specified: false,
+            card_details: None,
+            transaction_id: String::new(),
+        }
+    }
+
+    /// The transaction as paid with `card_number` at `merchant_name`, under
+    /// the card purchase id `transaction_id`.
+    pub fn with_card(
+        mut self,
+        card_number: &str,
+        merchant_name: &str,
+        transaction_id: &str,
+    ) -> TransactionObj {
+        self.card_details_specified = true;
+        self.card_details = Some(CardDetailsObj {
+            card_number: card_number.to_string(),
+            currency_amount: self.amount,
+            currency_rate: 1.0,
+            merchant_category_code: "5411".to_string(),
+            merchant_category_description: "Grocery Stores, Supermarkets".to_string(),
+            merchant_city: "OSLO".to_string(),
+            merchant_name: merchant_name.to_string(),
+            original_currency_code: "NOK".to_string(),
+            purchase_date: self.accounting_date.to_string(),
+            transaction_id: transaction_id.to_string(),
+        });
+        self
+    }
+
+    /// The transaction as a reservation that hasn't been booked yet.
+    pub fn reserved(mut self) -> TransactionObj {
+        self.is_reservation = true;
+        self.reservation_type = Some("CardReservation".to_string());
+        self
+    }
+}
+
+/// The `yyyy-mm-dd` part of a date given by the API.
+pub fn date(value: &str) -> &str {
+    match value.get(..10) {
+        Some(date) => date,
+        None => value,
+    }
+}
+
+/// A transaction tagged with the account it was made on, used when
+/// listing transactions across accounts.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountTransactionObj {
+    pub account_name: String,
+    pub account_number: String,
+    #[serde(flatten)]
+    pub transaction: TransactionObj,
+}
+
+impl fmt::Display for AccountTransactionObj {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Account:\t\t{} [nr: {}]",
+            self.account_name, self.account_number
+        )?;
+        write!(f, "{}", self.transaction)
+    }
+}
+
+impl AsRef<TransactionObj> for AccountTransactionObj {
+    fn as_ref(&self) -> &TransactionObj {
+        &self.transaction
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferRequest {
     pub from_account_id: String,
     pub to_account_id: String,
     pub message: String,
-    pub amount: f32,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub amount: Decimal,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferResponse {
     pub error_type: Option<String>,
     pub is_error: bool,
     pub error_message: Option<String>,
+    pub trace_id: Option<String>,
 }
 
 pub type Transactions = MultipleItems<TransactionObj>;
+pub type AccountTransactions = MultipleItems<AccountTransactionObj>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn money_round_trips_as_exact_json_numbers() {
+        let json = "{\"accountId\":\"1\",\"accountNumber\":\"97100000001\",\
+                    \"ownerCustomerId\":\"12345678901\",\"name\":\"Brukskonto\",\
+                    \"accountType\":\"Standard account\",\"available\":1234567.89,\
+                    \"balance\":0.1,\"creditLimit\":0}";
+
+        let account: AccountObj = serde_json::from_str(json).unwrap();
+        assert_eq!(account.available, Decimal::new(123456789, 2));
+        assert_eq!(account.balance, Decimal::new(1, 1));
+
+        assert_eq!(serde_json::to_string(&account).unwrap(), json);
+    }
+
+    #[test]
+    fn transfers_send_the_amount_as_a_number() {
+        let transfer = TransferRequest {
+            from_account_id: "1".to_string(),
+            to_account_id: "2".to_string(),
+            message: "Savings".to_string(),
+            amount: Decimal::new(1, 1),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&transfer).unwrap(),
+            "{\"fromAccountId\":\"1\",\"toAccountId\":\"2\",\"message\":\"Savings\",\"amount\":0.1}"
+        );
+    }
+}
@@ -1,31 +1,173 @@
 use chrono;
+use clap;
+use core::transfer::ValidationError;
+use csv;
 use regex;
 use reqwest;
+use rusqlite;
+use rustyline::error::ReadlineError;
+use serde_json;
 use std::convert;
 use std::error;
 use std::fmt;
+use std::io;
+use toml;
+use url;
 
 #[derive(Debug)]
 pub enum Error {
     Reqwest(reqwest::Error),
+    Clap(clap::Error),
+    Csv(csv::Error),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    Url(url::ParseError),
+    Readline(ReadlineError),
+    Sqlite(rusqlite::Error),
     ArgumentMissing(&'static str),
     Regex(regex::Error),
     NoAccountFound(&'static str),
-    EnvMissing(&'static str),
+    UnknownAccount(String),
+    UnknownTemplate(String),
+    AmbiguousAccount {
+        input: String,
+        candidates: Vec<String>,
+    },
+    CredentialMissing(&'static str),
+    /// `--offline` was given before anything was synced.
+    NotSynced,
+    Config(&'static str),
+    Auth(&'static str),
+    Api {
+        error_type: Option<String>,
+        message: Option<String>,
+        trace_id: Option<String>,
+        http_status: u16,
+    },
+    TransferRejected {
+        error_type: Option<String>,
+        message: Option<String>,
+        trace_id: Option<String>,
+    },
+    InvalidTransfer(ValidationError),
+    /// An error from one step of a longer check, e.g. `auth test`, naming
+    /// the step that failed.
+    Step(&'static str, Box<Error>),
     Parsable(&'static str),
     Message(&'static str),
 }
 
+/// Exit codes used by the binary, one per class of error.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_CONFIG: i32 = 3;
+pub const EXIT_AUTH: i32 = 4;
+pub const EXIT_NETWORK: i32 = 5;
+pub const EXIT_API: i32 = 6;
+pub const EXIT_TRANSFER_REJECTED: i32 = 7;
+pub const EXIT_INVALID_TRANSFER: i32 = 8;
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            Error::ArgumentMissing(_)
+            | Error::Parsable(_)
+            | Error::NoAccountFound(_)
+            | Error::UnknownAccount(_)
+            | Error::AmbiguousAccount { .. }
+            | Error::UnknownTemplate(_)
+            | Error::Clap(_)
+            | Error::NotSynced => EXIT_USAGE,
+            Error::CredentialMissing(_) | Error::Config(_) | Error::Toml(_) | Error::Url(_) => {
+                EXIT_CONFIG
+            }
+            Error::Auth(_) => EXIT_AUTH,
+            // The identity server is the only one answered without going
+            // through `read_json`, so a 4xx here means rejected credentials.
+            Error::Reqwest(ref err) => match err.status() {
+                Some(status) if status.is_client_error() => EXIT_AUTH,
+                Some(_) => EXIT_API,
+                // Only errors from the connection itself, e.g. a refused
+                // connection or a timeout, mean the network failed.
+                None if err.is_http() => EXIT_NETWORK,
+                // An answer came back but couldn't be read.
+                None if err.is_serialization() => EXIT_API,
+                None => EXIT_FAILURE,
+            },
+            Error::Api { .. } => EXIT_API,
+            Error::TransferRejected { .. } => EXIT_TRANSFER_REJECTED,
+            Error::InvalidTransfer(_) => EXIT_INVALID_TRANSFER,
+            Error::Step(_, ref err) => err.exit_code(),
+            Error::Csv(_)
+            | Error::Io(_)
+            | Error::Json(_)
+            | Error::Regex(_)
+            | Error::Readline(_)
+            | Error::Sqlite(_)
+            | Error::Message(_) => EXIT_FAILURE,
+        }
+    }
+
+    /// Short name of the error class, used in JSON error output.
+    pub fn kind(&self) -> &'static str {
+        match self.exit_code() {
+            EXIT_USAGE => "usage",
+            EXIT_CONFIG => "config",
+            EXIT_AUTH => "auth",
+            EXIT_NETWORK => "network",
+            EXIT_API => "api",
+            EXIT_TRANSFER_REJECTED => "transfer_rejected",
+            EXIT_INVALID_TRANSFER => "invalid_transfer",
+            _ => "failure",
+        }
+    }
+
+    pub fn trace_id(&self) -> Option<&str> {
+        match *self {
+            Error::Api { ref trace_id, .. } | Error::TransferRejected { ref trace_id, .. } => {
+                trace_id.as_ref().map(|trace_id| trace_id.as_str())
+            }
+            Error::Step(_, ref err) => err.trace_id(),
+            _ => None,
+        }
+    }
+}
+
 impl error::Error for Error {
     fn description(&self) -> &str {
         use self::*;
 
         match *self {
             Error::Reqwest(ref err) => err.description(),
+            Error::Clap(ref err) => err.description(),
+            Error::Csv(ref err) => err.description(),
+            Error::Io(ref err) => err.description(),
+            Error::Json(ref err) => err.description(),
+            Error::Toml(ref err) => err.description(),
+            Error::Url(ref err) => err.description(),
+            Error::Readline(ref err) => err.description(),
+            Error::Sqlite(ref err) => err.description(),
             Error::ArgumentMissing(ref err) => err,
             Error::Regex(ref err) => err.description(),
             Error::NoAccountFound(ref err) => err,
-            Error::EnvMissing(ref err) => err,
+            Error::UnknownAccount(_) => "no account matches the given name or number",
+            Error::UnknownTemplate(_) => "no template has the given name",
+            Error::AmbiguousAccount { .. } => "several accounts match the given name",
+            Error::CredentialMissing(ref err) => err,
+            Error::NotSynced => "nothing has been synced yet",
+            Error::Config(ref err) => err,
+            Error::Auth(ref err) => err,
+            Error::Api { ref message, .. } => match *message {
+                Some(ref message) => message.as_str(),
+                None => "the API returned an error",
+            },
+            Error::TransferRejected { ref message, .. } => match *message {
+                Some(ref message) => message.as_str(),
+                None => "the transfer was rejected",
+            },
+            Error::InvalidTransfer(ref err) => err.description(),
+            Error::Step(step, _) => step,
             Error::Parsable(ref err) => err,
             Error::Message(ref err) => err,
         }
@@ -36,10 +178,28 @@ impl error::Error for Error {
 
         match *self {
             Error::Reqwest(ref err) => Some(err),
+            Error::Clap(ref err) => Some(err),
             Error::Regex(ref err) => Some(err),
+            Error::Csv(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
+            Error::Json(ref err) => Some(err),
+            Error::Toml(ref err) => Some(err),
+            Error::Url(ref err) => Some(err),
+            Error::Readline(ref err) => Some(err),
+            Error::Sqlite(ref err) => Some(err),
             Error::ArgumentMissing(ref _err) => None,
             Error::NoAccountFound(ref _err) => None,
-            Error::EnvMissing(ref _err) => None,
+            Error::UnknownAccount(ref _err) => None,
+            Error::UnknownTemplate(ref _err) => None,
+            Error::AmbiguousAccount { .. } => None,
+            Error::CredentialMissing(ref _err) => None,
+            Error::NotSynced => None,
+            Error::Config(ref _err) => None,
+            Error::Auth(ref _err) => None,
+            Error::Api { .. } => None,
+            Error::TransferRejected { .. } => None,
+            Error::InvalidTransfer(_) => None,
+            Error::Step(_, ref err) => Some(err.as_ref()),
             Error::Parsable(ref _err) => None,
             Error::Message(ref _err) => None,
         }
@@ -52,10 +212,84 @@ impl fmt::Display for Error {
 
         match *self {
             Error::Reqwest(ref err) => write!(f, "Reqwest error: {}", err),
+            Error::Clap(ref err) => write!(f, "{}", err.message),
             Error::Regex(ref err) => write!(f, "Regex error: {}", err),
+            Error::Csv(ref err) => write!(f, "Csv error: {}", err),
+            Error::Io(ref err) => write!(f, "IO error: {}", err),
+            Error::Json(ref err) => write!(f, "Json error: {}", err),
+            Error::Toml(ref err) => write!(f, "Config error: {}", err),
+            Error::Url(ref err) => write!(f, "Url error: {}", err),
+            Error::Readline(ref err) => write!(f, "Readline error: {}", err),
+            Error::Sqlite(ref err) => write!(f, "Store error: {}", err),
             Error::ArgumentMissing(ref err) => write!(f, "Arg missing error: {}", err),
             Error::NoAccountFound(ref err) => write!(f, "No account found: {}", err),
-            Error::EnvMissing(ref err) => write!(f, "Error: env {} missing", err),
+            Error::UnknownAccount(ref input) => write!(
+                f,
+                "No account found: `{}` matches no alias, account number or name",
+                input
+            ),
+            Error::UnknownTemplate(ref name) => write!(
+                f,
+                "No template found: `{}`, see `template list` for the saved ones",
+                name
+            ),
+            Error::AmbiguousAccount {
+                ref input,
+                ref candidates,
+            } => write!(
+                f,
+                "Ambiguous account: `{}` matches {}",
+                input,
+                candidates.join(", ")
+            ),
+            Error::CredentialMissing(ref err) => write!(
+                f,
+                "Error: env {} missing and not set in the selected profile",
+                err
+            ),
+            Error::NotSynced => write!(
+                f,
+                "Nothing has been synced yet, run `sync` before using --offline"
+            ),
+            Error::Config(ref err) => write!(f, "Config error: {}", err),
+            Error::Auth(ref err) => write!(f, "Authentication error: {}", err),
+            Error::Api {
+                ref error_type,
+                ref message,
+                ref trace_id,
+                http_status,
+            } => {
+                write!(f, "API error (HTTP {})", http_status)?;
+                if let Some(ref error_type) = *error_type {
+                    write!(f, " {}", error_type)?;
+                }
+                if let Some(ref message) = *message {
+                    write!(f, ": {}", message)?;
+                }
+                if let Some(ref trace_id) = *trace_id {
+                    write!(f, "\nTrace id: {}", trace_id)?;
+                }
+                Ok(())
+            }
+            Error::TransferRejected {
+                ref error_type,
+                ref message,
+                ref trace_id,
+            } => {
+                write!(f, "Transfer rejected")?;
+                if let Some(ref error_type) = *error_type {
+                    write!(f, " {}", error_type)?;
+                }
+                if let Some(ref message) = *message {
+                    write!(f, ": {}", message)?;
+                }
+                if let Some(ref trace_id) = *trace_id {
+                    write!(f, "\nTrace id: {}", trace_id)?;
+                }
+                Ok(())
+            }
+            Error::InvalidTransfer(ref err) => write!(f, "Invalid transfer: {}", err),
+            Error::Step(step, ref err) => write!(f, "Couldn't {}: {}", step, err),
             Error::Parsable(ref err) => write!(f, "Parse error: {}", err),
             Error::Message(ref err) => write!(f, "Error: {}", err),
         }
@@ -64,7 +298,7 @@ impl fmt::Display for Error {
 
 impl convert::From<chrono::ParseError> for Error {
     fn from(_err: chrono::ParseError) -> Self {
-        Error::Message("Couldn't parse date on format `YYYY-MM-DD`")
+        Error::Parsable("couldn't parse date on format `YYYY-MM-DD`")
     }
 }
 
@@ -74,8 +308,56 @@ impl convert::From<reqwest::Error> for Error {
     }
 }
 
+impl convert::From<clap::Error> for Error {
+    fn from(err: clap::Error) -> Self {
+        Error::Clap(err)
+    }
+}
+
 impl convert::From<regex::Error> for Error {
     fn from(err: regex::Error) -> Self {
         Error::Regex(err)
     }
 }
+
+impl convert::From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Error::Csv(err)
+    }
+}
+
+impl convert::From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl convert::From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl convert::From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Error::Toml(err)
+    }
+}
+
+impl convert::From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Self {
+        Error::Url(err)
+    }
+}
+
+impl convert::From<ReadlineError> for Error {
+    fn from(err: ReadlineError) -> Self {
+        Error::Readline(err)
+    }
+}
+
+impl convert::From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
@@ -0,0 +1,194 @@
+use core::entities::{AccountTransactionObj, TransactionObj};
+use core::error::Error;
+use csv::WriterBuilder;
+use std::io;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+}
+
+impl ExportFormat {
+    pub fn delimiter(self) -> u8 {
+        match self {
+            ExportFormat::Csv => b',',
+            ExportFormat::Tsv => b'\t',
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(ExportFormat::Csv),
+            "tsv" => Ok(ExportFormat::Tsv),
+            _ => Err(Error::Parsable("format must be either csv or tsv")),
+        }
+    }
+}
+
+pub const COLUMNS: &[&str] = &[
+    "account_name",
+    "account_number",
+    "accounting_date",
+    "interest_date",
+    "amount",
+    "text",
+    "transaction_type",
+    "transaction_type_code",
+    "transaction_type_text",
+    "is_reservation",
+    "reservation_type",
+    "other_account_number",
+    "transaction_id",
+    "card_number",
+    "merchant_name",
+    "merchant_city",
+    "merchant_category_code",
+    "merchant_category_description",
+    "currency_amount",
+    "currency_rate",
+    "original_currency_code",
+    "purchase_date",
+];
+
+pub const DEFAULT_COLUMNS: &str = "accounting_date,amount,text,transaction_type_text,\
+                                   merchant_name,merchant_city,merchant_category_code,\
+                                   currency_amount,original_currency_code";
+
+pub const DEFAULT_ACCOUNT_COLUMNS: &str = "account_name,accounting_date,amount,text,\
+                                           transaction_type_text,merchant_name,merchant_city,\
+                                           merchant_category_code,currency_amount,\
+                                           original_currency_code";
+
+/// Parses a comma separated list of column names, keeping the given order.
+pub fn parse_columns(value: &str) -> Result<Vec<&'static str>, Error> {
+    let mut columns: Vec<&'static str> = Vec::new();
+
+    for name in value.split(',').map(|name| name.trim()) {
+        match COLUMNS.iter().find(|column| **column == name) {
+            Some(column) => columns.push(*column),
+            None => return Err(Error::Parsable("unknown column given to --columns")),
+        }
+    }
+
+    Ok(columns)
+}
+
+fn column_value(
+    account: Option<&AccountTransactionObj>,
+    transaction: &TransactionObj,
+    column: &str,
+) -> String {
+    let card = transaction.card_details.as_ref();
+
+    match column {
+        "account_name" => account
+            .map(|a| a.account_name.to_string())
+            .unwrap_or_default(),
+        "account_number" => account
+            .map(|a| a.account_number.to_string())
+            .unwrap_or_default(),
+        "accounting_date" => transaction.accounting_date.to_string(),
+        "interest_date" => transaction.interest_date.to_string(),
+        "amount" => transaction.amount.to_string(),
+        "text" => transaction.text.to_string(),
+        "transaction_type" => transaction.transaction_type.to_string(),
+        "transaction_type_code" => transaction.transaction_type_code.to_string(),
+        "transaction_type_text" => transaction.transaction_type_text.to_string(),
+        "is_reservation" => transaction.is_reservation.to_string(),
+        "reservation_type" => transaction.reservation_type.clone().unwrap_or_default(),
+        "other_account_number" => transaction.other_account_number.clone().unwrap_or_default(),
+        "transaction_id" => transaction.transaction_id.to_string(),
+        "card_number" => card.map(|c| c.card_number.to_string()).unwrap_or_default(),
+        "merchant_name" => card
+            .map(|c| c.merchant_name.to_string())
+            .unwrap_or_default(),
+        "merchant_city" => card
+            .map(|c| c.merchant_city.to_string())
+            .unwrap_or_default(),
+        "merchant_category_code" => card
+            .map(|c| c.merchant_category_code.to_string())
+            .unwrap_or_default(),
+        "merchant_category_description" => card
+            .map(|c| c.merchant_category_description.to_string())
+            .unwrap_or_default(),
+        "currency_amount" => card
+            .map(|c| c.currency_amount.to_string())
+            .unwrap_or_default(),
+        "currency_rate" => card
+            .map(|c| c.currency_rate.to_string())
+            .unwrap_or_default(),
+        "original_currency_code" => card
+            .map(|c| c.original_currency_code.to_string())
+            .unwrap_or_default(),
+        "purchase_date" => card
+            .map(|c| c.purchase_date.to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Writes a header row followed by one row per transaction.
+pub fn write_transactions<W: io::Write>(
+    writer: W,
+    transactions: &[TransactionObj],
+    columns: &[&str],
+    format: ExportFormat,
+) -> Result<(), Error> {
+    write_rows(
+        writer,
+        transactions.iter().map(|transaction| (None, transaction)),
+        columns,
+        format,
+    )
+}
+
+/// Writes a header row followed by one row per transaction, with the
+/// account columns filled in.
+pub fn write_account_transactions<W: io::Write>(
+    writer: W,
+    rows: &[AccountTransactionObj],
+    columns: &[&str],
+    format: ExportFormat,
+) -> Result<(), Error> {
+    write_rows(
+        writer,
+        rows.iter().map(|row| (Some(row), &row.transaction)),
+        columns,
+        format,
+    )
+}
+
+fn write_rows<'r, W, I>(
+    writer: W,
+    rows: I,
+    columns: &[&str],
+    format: ExportFormat,
+) -> Result<(), Error>
+where
+    W: io::Write,
+    I: Iterator<Item = (Option<&'r AccountTransactionObj>, &'r TransactionObj)>,
+{
+    let mut writer = WriterBuilder::new()
+        .delimiter(format.delimiter())
+        .from_writer(writer);
+
+    writer.write_record(columns)?;
+
+    for (account, transaction) in rows {
+        writer.write_record(
+            columns
+                .iter()
+                .map(|column| column_value(account, transaction, column)),
+        )?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
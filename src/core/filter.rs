@@ -0,0 +1,223 @@
+use core::entities::TransactionObj;
+use regex::Regex;
+use rust_decimal::Decimal;
+
+/// Narrows a list of transactions down to the ones of interest. Every
+/// criterion that is set must match; an empty filter keeps everything.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Matched against the text and the merchant name of card purchases.
+    pub grep: Option<Regex>,
+    /// Smallest size of the amount, whether money in or out.
+    pub min: Option<Decimal>,
+    /// Largest size of the amount, whether money in or out.
+    pub max: Option<Decimal>,
+    /// A transaction type code, or a transaction type text in any case.
+    pub transaction_type: Option<String>,
+    pub credits: bool,
+    pub debits: bool,
+    pub exclude_reservations: bool,
+    /// The last digits of the card number.
+    pub card: Option<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, transaction: &TransactionObj) -> bool {
+        if let Some(ref grep) = self.grep {
+            let merchant = transaction
+                .card_details
+                .as_ref()
+                .map_or("", |card| card.merchant_name.as_str());
+
+            if !grep.is_match(&transaction.text) && !grep.is_match(merchant) {
+                return false;
+            }
+        }
+
+        let size = transaction.amount.abs();
+
+        if self.min.map_or(false, |min| size < min) || self.max.map_or(false, |max| size > max) {
+            return false;
+        }
+
+        if let Some(ref transaction_type) = self.transaction_type {
+            let is_type = match transaction_type.parse::<i32>() {
+                Ok(code) => transaction.transaction_type_code == code,
+                Err(_) => {
+                    transaction.transaction_type_text.to_lowercase()
+                        == transaction_type.to_lowercase()
+                        || transaction.transaction_type.to_lowercase()
+                            == transaction_type.to_lowercase()
+                }
+            };
+
+            if !is_type {
+                return false;
+            }
+        }
+
+        let zero = Decimal::new(0, 0);
+
+        if (self.credits && transaction.amount <= zero)
+            || (self.debits && transaction.amount >= zero)
+        {
+            return false;
+        }
+
+        if self.exclude_reservations && transaction.is_reservation {
+            return false;
+        }
+
+        if let Some(ref card) = self.card {
+            match transaction.card_details {
+                Some(ref details) if details.card_number.ends_with(card.as_str()) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    pub fn apply(&self, transactions: &mut Vec<TransactionObj>) {
+        transactions.retain(|transaction| self.matches(transaction));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::RegexBuilder;
+
+    fn transaction(amount: Decimal, text: &str) -> TransactionObj {
+        TransactionObj::fixture("2018-09-20", amount, text)
+    }
+
+    fn card_purchase(amount: Decimal, merchant: &str, card_number: &str) -> TransactionObj {
+        transaction(amount, "*1234 20.09 NOK 250.00").with_card(card_number, merchant, "4321")
+    }
+
+    fn grep(pattern: &str) -> Option<Regex> {
+        Some(
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn an_empty_filter_keeps_everything() {
+        let filter = Filter::default();
+        assert!(filter.matches(&transaction(Decimal::new(-250, 0), "Rema 1000")));
+        assert!(filter.matches(&transaction(Decimal::new(25000, 0), "Lønn")));
+    }
+
+    #[test]
+    fn greps_the_text_and_the_merchant() {
+        let filter = Filter {
+            grep: grep("rema|kiwi"),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&transaction(Decimal::new(-250, 0), "REMA 1000 MAJORSTUEN")));
+        assert!(filter.matches(&card_purchase(Decimal::new(-250, 0), "Kiwi 505", "*1234")));
+        assert!(!filter.matches(&transaction(Decimal::new(-250, 0), "Meny")));
+    }
+
+    #[test]
+    fn compares_min_and_max_without_the_sign() {
+        let filter = Filter {
+            min: Some(Decimal::new(100, 0)),
+            max: Some(Decimal::new(500, 0)),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&transaction(Decimal::new(-100, 0), "out")));
+        assert!(filter.matches(&transaction(Decimal::new(500, 0), "in")));
+        assert!(!filter.matches(&transaction(Decimal::new(-9999, 2), "small out")));
+        assert!(!filter.matches(&transaction(Decimal::new(50001, 2), "large in")));
+    }
+
+    #[test]
+    fn keeps_only_credits_or_debits() {
+        let credits = Filter {
+            credits: true,
+            ..Filter::default()
+        };
+        let debits = Filter {
+            debits: true,
+            ..Filter::default()
+        };
+        let income = transaction(Decimal::new(25000, 0), "Lønn");
+        let expense = transaction(Decimal::new(-250, 0), "Rema 1000");
+        let zero = transaction(Decimal::new(0, 0), "Nothing");
+
+        assert!(credits.matches(&income));
+        assert!(!credits.matches(&expense));
+        assert!(!debits.matches(&income));
+        assert!(debits.matches(&expense));
+        assert!(!credits.matches(&zero));
+        assert!(!debits.matches(&zero));
+    }
+
+    #[test]
+    fn matches_the_type_by_code_or_text() {
+        let expense = transaction(Decimal::new(-250, 0), "Rema 1000");
+        for transaction_type in &["714", "varekjøp", "VAREKJØP"] {
+            let filter = Filter {
+                transaction_type: Some(transaction_type.to_string()),
+                ..Filter::default()
+            };
+            assert!(filter.matches(&expense), "{}", transaction_type);
+        }
+
+        let filter = Filter {
+            transaction_type: Some("200".to_string()),
+            ..Filter::default()
+        };
+        assert!(!filter.matches(&expense));
+    }
+
+    #[test]
+    fn leaves_out_reservations() {
+        let filter = Filter {
+            exclude_reservations: true,
+            ..Filter::default()
+        };
+        let reservation = transaction(Decimal::new(-250, 0), "Rema 1000").reserved();
+
+        assert!(!filter.matches(&reservation));
+        assert!(filter.matches(&transaction(Decimal::new(-250, 0), "Rema 1000")));
+    }
+
+    #[test]
+    fn matches_the_end_of_the_card_number() {
+        let filter = Filter {
+            card: Some("1234".to_string()),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&card_purchase(Decimal::new(-250, 0), "Kiwi", "*1234")));
+        assert!(!filter.matches(&card_purchase(Decimal::new(-250, 0), "Kiwi", "*9876")));
+        assert!(!filter.matches(&transaction(Decimal::new(-250, 0), "Rema 1000")));
+    }
+
+    #[test]
+    fn applies_every_criterion() {
+        let filter = Filter {
+            grep: grep("rema"),
+            debits: true,
+            min: Some(Decimal::new(200, 0)),
+            ..Filter::default()
+        };
+        let mut transactions = vec![
+            transaction(Decimal::new(-250, 0), "Rema 1000"),
+            transaction(Decimal::new(-150, 0), "Rema 1000"),
+            transaction(Decimal::new(250, 0), "Rema 1000 refund"),
+            transaction(Decimal::new(-250, 0), "Kiwi"),
+        ];
+
+        filter.apply(&mut transactions);
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount, Decimal::new(-250, 0));
+        assert_eq!(transactions[0].text, "Rema 1000");
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::{Datelike, NaiveDate};
+use core::entities::{date, TransactionObj};
+use core::error::Error;
+use core::sort::merchant;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GroupBy {
+    Day,
+    Week,
+    Month,
+    Merchant,
+    Type,
+}
+
+impl FromStr for GroupBy {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "day" => Ok(GroupBy::Day),
+            "week" => Ok(GroupBy::Week),
+            "month" => Ok(GroupBy::Month),
+            "merchant" => Ok(GroupBy::Merchant),
+            "type" => Ok(GroupBy::Type),
+            _ => Err(Error::Parsable(
+                "group-by must be one of day, week, month, merchant or type",
+            )),
+        }
+    }
+}
+
+impl GroupBy {
+    /// The group a transaction falls in, e.g. `2018-09-20`, `2018-W38` or
+    /// `2018-09` for the dates.
+    fn key(self, transaction: &TransactionObj) -> String {
+        let day = date(&transaction.accounting_date);
+
+        match self {
+            GroupBy::Day => day.to_string(),
+            GroupBy::Week => match NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+                Ok(day) => format!("{}-W{:02}", day.iso_week().year(), day.iso_week().week()),
+                Err(_) => day.to_string(),
+            },
+            GroupBy::Month => match day.get(..7) {
+                Some(month) => month.to_string(),
+                None => day.to_string(),
+            },
+            GroupBy::Merchant => merchant(transaction).to_string(),
+            GroupBy::Type => transaction.transaction_type_text.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group<'a, T: 'a> {
+    pub key: String,
+    pub count: usize,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub subtotal: Decimal,
+    pub transactions: Vec<&'a T>,
+}
+
+/// Transactions in groups, each with a subtotal, and the total of them all.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Grouped<'a, T: 'a> {
+    pub groups: Vec<Group<'a, T>>,
+    pub count: usize,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub total: Decimal,
+}
+
+impl<'a, T: AsRef<TransactionObj>> fmt::Display for Grouped<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for group in &self.groups {
+            writeln!(f, "{}", group.key)?;
+            for row in &group.transactions {
+                let transaction: &TransactionObj = row.as_ref();
+                writeln!(
+                    f,
+                    "  {}\t{}\t{}",
+                    date(&transaction.accounting_date),
+                    transaction.amount,
+                    transaction.text
+                )?;
+            }
+            writeln!(
+                f,
+                "  Subtotal:\t{}\t({} transactions)\n",
+                group.subtotal, group.count
+            )?;
+        }
+
+        write!(f, "Total:\t\t{}\t({} transactions)", self.total, self.count)
+    }
+}
+
+/// Groups the transactions, keeping the groups in the order their first
+/// transaction appears, so sorted transactions give sorted groups.
+pub fn group<T: AsRef<TransactionObj>>(rows: &[T], by: GroupBy) -> Grouped<T> {
+    let mut groups: Vec<Group<T>> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut total = Decimal::new(0, 0);
+
+    for row in rows {
+        let transaction: &TransactionObj = row.as_ref();
+        let key = by.key(transaction);
+
+        let i = match index.get(&key) {
+            Some(&i) => i,
+            None => {
+                groups.push(Group {
+                    key: key.to_string(),
+                    count: 0,
+                    subtotal: Decimal::new(0, 0),
+                    transactions: Vec::new(),
+                });
+                index.insert(key, groups.len() - 1);
+                groups.len() - 1
+            }
+        };
+
+        groups[i].count += 1;
+        groups[i].subtotal += transaction.amount;
+        groups[i].transactions.push(row);
+        total += transaction.amount;
+    }
+
+    Grouped {
+        groups,
+        count: rows.len(),
+        total,
+    }
+}
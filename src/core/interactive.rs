@@ -1,50 +1,135 @@
 use core::entities::AccountObj;
 use core::error::Error;
+use core::picker;
 use io;
 use regex::Regex;
+use std::io::Write;
 use std::process::{Command, Stdio};
+use std::str::FromStr;
 
+/// Which fuzzy-matcher to pick accounts with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Picker {
+    Fzf,
+    Skim,
+    Builtin,
+}
+
+impl Picker {
+    fn command(self) -> Option<&'static str> {
+        match self {
+            Picker::Fzf => Some("fzf"),
+            Picker::Skim => Some("sk"),
+            Picker::Builtin => None,
+        }
+    }
+}
+
+impl FromStr for Picker {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fzf" => Ok(Picker::Fzf),
+            "skim" => Ok(Picker::Skim),
+            "builtin" => Ok(Picker::Builtin),
+            _ => Err(Error::Config("picker must be one of fzf, skim or builtin")),
+        }
+    }
+}
+
+/// Lets the user pick one of `accounts`.
 pub fn fuzzy_match_account<'a>(
     accounts: &'a [AccountObj],
     header: &str,
+    picker: Option<Picker>,
 ) -> Result<&'a AccountObj, Error> {
-    let mut fzf = Command::new("fzf")
+    let options: Vec<String> = accounts.iter().map(account_option).collect();
+
+    match fuzzy_select(&options, header, picker)? {
+        Some(index) => Ok(&accounts[index]),
+        None => Err(Error::NoAccountFound("no account was selected")),
+    }
+}
+
+// Finds the account number in a line picked by an external picker.
+const ACCOUNT_NUMBER: &str = r"\[nr: (?P<account_nr>\w+)\]";
+
+/// How an account is listed in the picker.
+pub fn account_option(account: &AccountObj) -> String {
+    format!("{}\t\t[nr: {}]", account.name, account.account_number)
+}
+
+/// Lets the user pick one of `options`, returning its index or `None`
+/// when nothing was picked. Without a configured picker `fzf` is used when
+/// installed, and the built-in picker otherwise.
+pub fn fuzzy_select(
+    options: &[String],
+    header: &str,
+    picker: Option<Picker>,
+) -> Result<Option<usize>, Error> {
+    let command = match picker {
+        Some(picker) => picker.command(),
+        None => Some("fzf"),
+    };
+
+    if let Some(command) = command {
+        match external_select(command, options, header) {
+            Err(Error::Io(ref err))
+                if picker.is_none() && err.kind() == io::ErrorKind::NotFound => {}
+            result => return result,
+        }
+    }
+
+    picker::pick(options, header)
+}
+
+fn external_select(
+    command: &str,
+    options: &[String],
+    header: &str,
+) -> Result<Option<usize>, Error> {
+    let mut fzf = Command::new(command)
         .arg("--header")
         .arg(header)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .spawn()
-        .expect("failed to spawn `fzf`");
+        .spawn()?;
 
     let mut input = String::new();
-    for account in accounts {
-        input.push_str(&format!(
-            "{}\t\t[nr: {}]\n",
-            account.name, account.account_number
-        ))
+    for option in options {
+        input.push_str(option);
+        input.push('\n');
     }
 
-    io::copy(&mut input.as_bytes(), fzf.stdin.as_mut().unwrap())
-        .expect("Could not pipe stdin to fzf process");
-    let output = fzf.wait_with_output().unwrap();
-
-    let account_picked = String::from_utf8(output.stdout).unwrap();
+    io::copy(&mut input.as_bytes(), fzf.stdin.as_mut().unwrap())?;
+    let output = fzf.wait_with_output()?;
 
-    let re = Regex::new(r".+\[nr: (?P<account_nr>\w+)\]")?;
-    let caps = match re.captures(&account_picked) {
-        Some(capture) => capture,
-        None => return Err(Error::Message("couldn't find a capture")),
-    };
+    let picked = String::from_utf8_lossy(&output.stdout);
+    let picked = picked.trim();
 
-    if let Some(account_nr) = caps.name("account_nr") {
-        for account in accounts {
-            if account.account_number == account_nr.as_str() {
-                return Ok(account);
-            }
-        }
+    // The picker may trim, align or strip colors from the line it prints,
+    // so accounts are found by their number rather than the whole line.
+    let re = Regex::new(ACCOUNT_NUMBER)?;
+    if let Some(caps) = re.captures(picked) {
+        let number = format!("[nr: {}]", &caps["account_nr"]);
+        return Ok(options.iter().position(|option| option.ends_with(&number)));
     }
 
-    Err(Error::NoAccountFound("Could not an account"))
+    Ok(options.iter().position(|option| option.trim() == picked))
+}
+
+/// Asks `question` on stderr and reads the answer from stdin. Only `y` or
+/// `yes` is taken as a yes, so an empty line or a closed stdin declines.
+pub fn confirm(question: &str) -> Result<bool, Error> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
 }
 
 pub fn remove_account<'a>(
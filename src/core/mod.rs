@@ -1,7 +1,27 @@
 pub mod authorize;
 pub mod bank;
+pub mod batch;
+pub mod cache;
+pub mod config;
 pub mod credentials;
 pub mod customers;
 pub mod entities;
 pub mod error;
+pub mod export;
+pub mod filter;
+pub mod group;
 pub mod interactive;
+pub mod money;
+pub mod output;
+pub mod picker;
+pub mod report;
+pub mod reservations;
+pub mod resolve;
+pub mod response;
+pub mod secret;
+pub mod shell;
+pub mod sort;
+pub mod store;
+pub mod template;
+pub mod transfer;
+pub mod tui;
@@ -0,0 +1,25 @@
+use core::error::Error;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Parses an amount given on the command line, e.g. `1500` or `1234.50`.
+///
+/// Amounts are parsed straight into a `Decimal`, so `0.1` is sent to the
+/// API as exactly `0.1`. More than two decimals are rejected, as a
+/// transfer can't be made in fractions of an øre.
+pub fn parse_amount(value: &str) -> Result<Decimal, Error> {
+    let amount = match Decimal::from_str(value.trim()) {
+        Ok(amount) => amount,
+        Err(_) => {
+            return Err(Error::Parsable(
+                "amount couldn't be parsed to a decimal value",
+            ))
+        }
+    };
+
+    if amount.normalize().scale() > 2 {
+        return Err(Error::Parsable("amount can't have more than two decimals"));
+    }
+
+    Ok(amount)
+}
@@ -0,0 +1,119 @@
+use core::entities::MultipleItems;
+use core::error::Error;
+use serde::Serialize;
+use serde_json;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(Error::Parsable(
+                "output must be one of text, json or ndjson",
+            )),
+        }
+    }
+}
+
+/// Prints a single value, either through its `Display` implementation or
+/// as JSON.
+pub fn print<T: Serialize + fmt::Display>(value: &T, format: OutputFormat) -> Result<(), Error> {
+    match format {
+        OutputFormat::Text => println!("{}", value),
+        _ => print_json(value, format)?,
+    }
+
+    Ok(())
+}
+
+/// Prints a value as JSON. `Ndjson` prints the value as one compact line.
+pub fn print_json<T: Serialize>(value: &T, format: OutputFormat) -> Result<(), Error> {
+    match format {
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(value)?),
+        _ => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+
+    Ok(())
+}
+
+/// Prints a list response. `Json` keeps the full response envelope, while
+/// `Ndjson` prints each item on a line of its own.
+pub fn print_items<T: Serialize + fmt::Display>(
+    items: &MultipleItems<T>,
+    format: OutputFormat,
+) -> Result<(), Error> {
+    match format {
+        OutputFormat::Ndjson => {
+            for item in &items.items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+        _ => print(items, format)?,
+    }
+
+    Ok(())
+}
+
+/// Prints a list of values, one per paragraph as text, or as a JSON
+/// array. `Ndjson` prints each value on a line of its own.
+pub fn print_list<T: Serialize + fmt::Display>(
+    values: &[T],
+    format: OutputFormat,
+) -> Result<(), Error> {
+    match format {
+        OutputFormat::Text => {
+            for value in values {
+                println!("{}\n", value);
+            }
+        }
+        OutputFormat::Json => print_json(&values, format)?,
+        OutputFormat::Ndjson => {
+            for value in values {
+                println!("{}", serde_json::to_string(value)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorReport<'a> {
+    error: &'static str,
+    message: String,
+    exit_code: i32,
+    trace_id: Option<&'a str>,
+}
+
+/// Prints an error on stderr, as a JSON object when JSON output is selected.
+pub fn print_error(err: &Error, format: OutputFormat) {
+    if format == OutputFormat::Text {
+        eprintln!("{}", err);
+        return;
+    }
+
+    let report = ErrorReport {
+        error: err.kind(),
+        message: err.to_string(),
+        exit_code: err.exit_code(),
+        trace_id: err.trace_id(),
+    };
+
+    match serde_json::to_string(&report) {
+        Ok(report) => eprintln!("{}", report),
+        Err(_) => eprintln!("{}", err),
+    }
+}
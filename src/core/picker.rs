@@ -0,0 +1,133 @@
+use core::error::Error;
+use std::io::Write;
+use termion::clear;
+use termion::cursor;
+use termion::event::Key;
+use termion::get_tty;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::style;
+use termion::terminal_size;
+
+/// Lets the user pick one of `options` in a full-screen list drawn on the
+/// terminal, filtered as they type. Returns the index of the picked option,
+/// or `None` when the user cancels with Esc or Ctrl-C.
+///
+/// Everything is drawn on and read from the controlling terminal, so
+/// stdout can still be piped.
+pub fn pick(options: &[String], header: &str) -> Result<Option<usize>, Error> {
+    let tty = get_tty()?;
+    let keys = tty.try_clone()?.keys();
+    let mut screen = AlternateScreen::from(tty.into_raw_mode()?);
+
+    let mut query = String::new();
+    let mut selected: usize = 0;
+    let mut matches: Vec<usize> = filter(options, &query);
+
+    draw(&mut screen, options, &matches, header, &query, selected)?;
+
+    for key in keys {
+        match key? {
+            Key::Char('\n') => {
+                let picked = matches.get(selected).cloned();
+                write!(screen, "{}", cursor::Show)?;
+                return Ok(picked);
+            }
+            Key::Esc | Key::Ctrl('c') => break,
+            Key::Up | Key::Ctrl('p') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Ctrl('n') => {
+                if selected + 1 < matches.len() {
+                    selected += 1;
+                }
+            }
+            Key::Backspace => {
+                query.pop();
+                matches = filter(options, &query);
+                selected = 0;
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                matches = filter(options, &query);
+                selected = 0;
+            }
+            _ => {}
+        }
+
+        draw(&mut screen, options, &matches, header, &query, selected)?;
+    }
+
+    write!(screen, "{}", cursor::Show)?;
+    Ok(None)
+}
+
+/// Indexes of the options containing every character of `query` in order,
+/// ignoring case.
+fn filter(options: &[String], query: &str) -> Vec<usize> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+
+    options
+        .iter()
+        .enumerate()
+        .filter(|&(_, option)| {
+            let mut wanted = query.iter().peekable();
+            for c in option.to_lowercase().chars() {
+                if wanted.peek() == Some(&&c) {
+                    wanted.next();
+                }
+            }
+            wanted.peek().is_none()
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn draw<W: Write>(
+    screen: &mut W,
+    options: &[String],
+    matches: &[usize],
+    header: &str,
+    query: &str,
+    selected: usize,
+) -> Result<(), Error> {
+    let (_, height) = terminal_size()?;
+    // Two lines are taken by the prompt and the header.
+    let rows = height.saturating_sub(2) as usize;
+    let skip = if selected >= rows {
+        selected + 1 - rows
+    } else {
+        0
+    };
+
+    write!(
+        screen,
+        "{}{}{}> {}\r\n{}{}/{} {}{}\r\n",
+        clear::All,
+        cursor::Goto(1, 1),
+        cursor::Hide,
+        query,
+        style::Faint,
+        matches.len(),
+        options.len(),
+        header,
+        style::Reset
+    )?;
+
+    for (row, &index) in matches.iter().enumerate().skip(skip).take(rows) {
+        if row == selected {
+            write!(
+                screen,
+                "{}{}{}\r\n",
+                style::Invert,
+                options[index],
+                style::Reset
+            )?;
+        } else {
+            write!(screen, "{}\r\n", options[index])?;
+        }
+    }
+
+    screen.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,580 @@
+use chrono::{Datelike, NaiveDate};
+use core::entities::{date, AccountTransactionObj, TransactionObj};
+use core::error::Error;
+use core::export::ExportFormat;
+use csv::WriterBuilder;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+/// How many of the largest expenses are listed for each month.
+pub const LARGEST_EXPENSES: usize = 3;
+
+/// The transaction type code of a transfer made in the online bank
+/// (`OVFNETTB`).
+pub const TRANSFER_TYPE_CODE: i32 = 200;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expense {
+    pub date: String,
+    pub text: String,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub amount: Decimal,
+}
+
+/// The difference from the month before.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub income: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub expenses: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub net: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthSummary {
+    /// The calendar month, e.g. `2018-09`.
+    pub month: String,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub income: Decimal,
+    /// Money going out, as a positive amount.
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub expenses: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub net: Decimal,
+    pub transactions: usize,
+    pub largest_expenses: Vec<Expense>,
+    /// Missing for the first month of the report.
+    pub change: Option<Change>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub from: String,
+    pub to: String,
+    pub months: Vec<MonthSummary>,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub income: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub expenses: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub net: Decimal,
+    /// Transfers between the summarized accounts, left out of the sums.
+    pub internal_transfers: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Summary from {} to {}\n", self.from, self.to)?;
+        writeln!(
+            f,
+            "{:<8}  {:>12}  {:>12}  {:>12}  {:>12}",
+            "Month", "Income", "Expenses", "Net", "Net change"
+        )?;
+
+        for month in &self.months {
+            writeln!(
+                f,
+                "{:<8}  {:>12}  {:>12}  {:>12}  {:>12}",
+                month.month,
+                month.income.to_string(),
+                month.expenses.to_string(),
+                month.net.to_string(),
+                month
+                    .change
+                    .as_ref()
+                    .map_or(String::new(), |change| format!("{:+}", change.net)),
+            )?;
+        }
+
+        writeln!(
+            f,
+            "{:<8}  {:>12}  {:>12}  {:>12}",
+            "Total",
+            self.income.to_string(),
+            self.expenses.to_string(),
+            self.net.to_string()
+        )?;
+
+        if self.internal_transfers > 0 {
+            writeln!(
+                f,
+                "\n{} transfers between your own accounts were left out",
+                self.internal_transfers
+            )?;
+        }
+
+        write!(f, "\nLargest expenses")?;
+        for month in &self.months {
+            if month.largest_expenses.is_empty() {
+                continue;
+            }
+            write!(f, "\n{}", month.month)?;
+            for expense in &month.largest_expenses {
+                write!(
+                    f,
+                    "\n  {}\t{}\t{}",
+                    expense.date, expense.amount, expense.text
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Sums the transactions up per calendar month from `from` to `to`. Every
+/// month in the range is listed, those without transactions as well, so
+/// the change from the month before is always from the previous month.
+pub fn summarize<T: AsRef<TransactionObj>>(rows: &[T], from: NaiveDate, to: NaiveDate) -> Summary {
+    let mut months: Vec<MonthSummary> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let zero = Decimal::new(0, 0);
+
+    let (mut year, mut month) = (from.year(), from.month());
+    while (year, month) <= (to.year(), to.month()) {
+        let key = format!("{:04}-{:02}", year, month);
+        index.insert(key.to_string(), months.len());
+        months.push(MonthSummary {
+            month: key,
+            income: zero,
+            expenses: zero,
+            net: zero,
+            transactions: 0,
+            largest_expenses: Vec::new(),
+            change: None,
+        });
+
+        if month == 12 {
+            year += 1;
+            month = 1;
+        } else {
+            month += 1;
+        }
+    }
+
+    let mut expenses: Vec<Vec<&TransactionObj>> = months.iter().map(|_| Vec::new()).collect();
+
+    for row in rows {
+        let transaction: &TransactionObj = row.as_ref();
+        let i = match date(&transaction.accounting_date)
+            .get(..7)
+            .and_then(|month| index.get(month))
+        {
+            Some(&i) => i,
+            None => continue,
+        };
+
+        let summary = &mut months[i];
+        summary.transactions += 1;
+        summary.net += transaction.amount;
+        if transaction.amount.is_sign_negative() {
+            summary.expenses -= transaction.amount;
+            expenses[i].push(transaction);
+        } else {
+            summary.income += transaction.amount;
+        }
+    }
+
+    for (i, mut largest) in expenses.into_iter().enumerate() {
+        largest.sort_by(|a, b| a.amount.cmp(&b.amount));
+        months[i].largest_expenses = largest
+            .into_iter()
+            .take(LARGEST_EXPENSES)
+            .map(|transaction| Expense {
+                date: date(&transaction.accounting_date).to_string(),
+                text: transaction.text.to_string(),
+                amount: transaction.amount,
+            })
+            .collect();
+
+        if i > 0 {
+            let change = Change {
+                income: months[i].income - months[i - 1].income,
+                expenses: months[i].expenses - months[i - 1].expenses,
+                net: months[i].net - months[i - 1].net,
+            };
+            months[i].change = Some(change);
+        }
+    }
+
+    Summary {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        income: months.iter().fold(zero, |sum, month| sum + month.income),
+        expenses: months.iter().fold(zero, |sum, month| sum + month.expenses),
+        net: months.iter().fold(zero, |sum, month| sum + month.net),
+        months,
+        internal_transfers: 0,
+    }
+}
+
+/// Removes transfers between the accounts in `rows`, which would
+/// otherwise count as both income and an expense. A transfer is the
+/// money going out of one account and the same amount coming into another
+/// on the same accounting date, where one side names the account on the
+/// other side, or both sides are transfers made in the online bank and
+/// neither names some other account. Returns the number of transfers
+/// removed.
+pub fn remove_internal_transfers(rows: &mut Vec<AccountTransactionObj>) -> usize {
+    let mut incoming: HashMap<(String, Decimal), Vec<usize>> = HashMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        if row.transaction.amount.is_sign_positive() {
+            incoming
+                .entry((
+                    date(&row.transaction.accounting_date).to_string(),
+                    row.transaction.amount,
+                ))
+                .or_insert_with(Vec::new)
+                .push(i);
+        }
+    }
+
+    let mut internal: Vec<bool> = vec![false; rows.len()];
+    let mut transfers = 0;
+
+    for (i, row) in rows.iter().enumerate() {
+        let transaction = &row.transaction;
+        if !transaction.amount.is_sign_negative() {
+            continue;
+        }
+
+        let key = (
+            date(&transaction.accounting_date).to_string(),
+            -transaction.amount,
+        );
+        let candidates = match incoming.get(&key) {
+            Some(candidates) => candidates,
+            None => continue,
+        };
+
+        let counterpart = candidates.iter().cloned().find(|&j| {
+            let other = &rows[j];
+            !internal[j] && other.account_number != row.account_number && is_transfer(row, other)
+        });
+
+        if let Some(j) = counterpart {
+            internal[i] = true;
+            internal[j] = true;
+            transfers += 1;
+        }
+    }
+
+    let mut flags = internal.into_iter();
+    rows.retain(|_| !flags.next().unwrap_or(false));
+
+    transfers
+}
+
+// Whether `out` and `into`, the same amount on the same day, are a
+// transfer between their accounts.
+fn is_transfer(out: &AccountTransactionObj, into: &AccountTransactionObj) -> bool {
+    match (
+        names(&out.transaction, &into.account_number),
+        names(&into.transaction, &out.account_number),
+    ) {
+        (Some(false), _) | (_, Some(false)) => false,
+        (Some(true), _) | (_, Some(true)) => true,
+        (None, None) => {
+            out.transaction.transaction_type_code == TRANSFER_TYPE_CODE
+                && into.transaction.transaction_type_code == TRANSFER_TYPE_CODE
+        }
+    }
+}
+
+// Whether the transaction names `account_number` as the other account, or
+// `None` when it doesn't name one.
+fn names(transaction: &TransactionObj, account_number: &str) -> Option<bool> {
+    match transaction.other_account_number {
+        Some(ref other) if transaction.other_account_number_specified && !other.is_empty() => {
+            Some(other == account_number)
+        }
+        _ => None,
+    }
+}
+
+/// Writes a header row followed by one row per month. The largest
+/// expenses are joined into one column.
+pub fn write_summary<W: io::Write>(
+    writer: W,
+    summary: &Summary,
+    format: ExportFormat,
+) -> Result<(), Error> {
+    let mut writer = WriterBuilder::new()
+        .delimiter(format.delimiter())
+        .from_writer(writer);
+
+    writer.write_record(&[
+        "month",
+        "income",
+        "expenses",
+        "net",
+        "transactions",
+        "income_change",
+        "expenses_change",
+        "net_change",
+        "largest_expenses",
+    ])?;
+
+    for month in &summary.months {
+        let change = |value: fn(&Change) -> Decimal| {
+            month
+                .change
+                .as_ref()
+                .map_or(String::new(), |change| value(change).to_string())
+        };
+
+        let largest: Vec<String> = month
+            .largest_expenses
+            .iter()
+            .map(|expense| format!("{} {}", expense.amount, expense.text))
+            .collect();
+
+        writer.write_record(&[
+            month.month.to_string(),
+            month.income.to_string(),
+            month.expenses.to_string(),
+            month.net.to_string(),
+            month.transactions.to_string(),
+            change(|change| change.income),
+            change(|change| change.expenses),
+            change(|change| change.net),
+            largest.join("; "),
+        ])?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(date: &str, amount: Decimal, text: &str) -> TransactionObj {
+        let mut transaction = TransactionObj::fixture(date, amount, text);
+        transaction.transaction_type = "OVFNETTB".to_string();
+        transaction.transaction_type_code = TRANSFER_TYPE_CODE;
+        transaction.transaction_type_text = "Overføring".to_string();
+        transaction
+    }
+
+    fn on_account(account_number: &str, transaction: TransactionObj) -> AccountTransactionObj {
+        AccountTransactionObj {
+            account_name: format!("Account {}", account_number),
+            account_number: account_number.to_string(),
+            transaction,
+        }
+    }
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn sums_up_each_calendar_month() {
+        let transactions = vec![
+            TransactionObj::fixture("2018-01-25", Decimal::new(25000, 0), "Lønn"),
+            TransactionObj::fixture("2018-01-26", Decimal::new(-8000, 0), "Husleie"),
+            TransactionObj::fixture("2018-01-31", Decimal::new(-25050, 2), "Rema 1000"),
+            TransactionObj::fixture("2018-02-01", Decimal::new(-100, 0), "Kiwi"),
+        ];
+
+        let summary = summarize(&transactions, day("2018-01-01"), day("2018-02-28"));
+
+        assert_eq!(summary.months.len(), 2);
+
+        let january = &summary.months[0];
+        assert_eq!(january.month, "2018-01");
+        assert_eq!(january.transactions, 3);
+        assert_eq!(january.income, Decimal::new(25000, 0));
+        assert_eq!(january.expenses, Decimal::new(825050, 2));
+        assert_eq!(january.net, Decimal::new(1674950, 2));
+
+        let february = &summary.months[1];
+        assert_eq!(february.month, "2018-02");
+        assert_eq!(february.income, Decimal::new(0, 0));
+        assert_eq!(february.expenses, Decimal::new(100, 0));
+        assert_eq!(february.net, Decimal::new(-100, 0));
+
+        assert_eq!(summary.income, Decimal::new(25000, 0));
+        assert_eq!(summary.expenses, Decimal::new(835050, 2));
+        assert_eq!(summary.net, Decimal::new(1664950, 2));
+    }
+
+    #[test]
+    fn lists_months_without_transactions_across_the_new_year() {
+        let transactions = vec![TransactionObj::fixture(
+            "2018-01-10",
+            Decimal::new(-100, 0),
+            "Kiwi",
+        )];
+
+        let summary = summarize(&transactions, day("2017-11-15"), day("2018-01-10"));
+
+        let months: Vec<&str> = summary.months.iter().map(|m| m.month.as_str()).collect();
+        assert_eq!(months, vec!["2017-11", "2017-12", "2018-01"]);
+        assert_eq!(summary.months[0].transactions, 0);
+        assert_eq!(summary.months[2].transactions, 1);
+    }
+
+    #[test]
+    fn compares_each_month_with_the_month_before() {
+        let transactions = vec![
+            TransactionObj::fixture("2018-01-25", Decimal::new(20000, 0), "Lønn"),
+            TransactionObj::fixture("2018-01-26", Decimal::new(-5000, 0), "Husleie"),
+            TransactionObj::fixture("2018-03-25", Decimal::new(22000, 0), "Lønn"),
+            TransactionObj::fixture("2018-03-26", Decimal::new(-6000, 0), "Husleie"),
+        ];
+
+        let summary = summarize(&transactions, day("2018-01-01"), day("2018-03-31"));
+
+        assert!(summary.months[0].change.is_none());
+
+        // February is empty, so it drops by all of January.
+        let february = summary.months[1].change.as_ref().unwrap();
+        assert_eq!(february.income, Decimal::new(-20000, 0));
+        assert_eq!(february.expenses, Decimal::new(-5000, 0));
+        assert_eq!(february.net, Decimal::new(-15000, 0));
+
+        let march = summary.months[2].change.as_ref().unwrap();
+        assert_eq!(march.income, Decimal::new(22000, 0));
+        assert_eq!(march.expenses, Decimal::new(6000, 0));
+        assert_eq!(march.net, Decimal::new(16000, 0));
+    }
+
+    #[test]
+    fn ignores_transactions_outside_the_months() {
+        let transactions = vec![
+            TransactionObj::fixture("2017-12-31", Decimal::new(-100, 0), "Before"),
+            TransactionObj::fixture("2018-01-15", Decimal::new(-200, 0), "Within"),
+            TransactionObj::fixture("2018-02-01", Decimal::new(-300, 0), "After"),
+        ];
+
+        let summary = summarize(&transactions, day("2018-01-01"), day("2018-01-31"));
+
+        assert_eq!(summary.months.len(), 1);
+        assert_eq!(summary.months[0].transactions, 1);
+        assert_eq!(summary.expenses, Decimal::new(200, 0));
+    }
+
+    #[test]
+    fn lists_the_largest_expenses_first() {
+        let transactions = vec![
+            TransactionObj::fixture("2018-01-02", Decimal::new(-50, 0), "Kiwi"),
+            TransactionObj::fixture("2018-01-03", Decimal::new(-8000, 0), "Husleie"),
+            TransactionObj::fixture("2018-01-04", Decimal::new(25000, 0), "Lønn"),
+            TransactionObj::fixture("2018-01-05", Decimal::new(-1200, 0), "Strøm"),
+            TransactionObj::fixture("2018-01-06", Decimal::new(-300, 0), "Rema 1000"),
+        ];
+
+        let summary = summarize(&transactions, day("2018-01-01"), day("2018-01-31"));
+
+        let largest: Vec<&str> = summary.months[0]
+            .largest_expenses
+            .iter()
+            .map(|expense| expense.text.as_str())
+            .collect();
+        assert_eq!(largest.len(), LARGEST_EXPENSES);
+        assert_eq!(largest, vec!["Husleie", "Strøm", "Rema 1000"]);
+    }
+
+    #[test]
+    fn removes_transfers_between_own_accounts() {
+        let mut rows = vec![
+            on_account(
+                "1",
+                transfer("2018-01-10", Decimal::new(-2000, 0), "Til sparing"),
+            ),
+            on_account(
+                "2",
+                transfer("2018-01-10", Decimal::new(2000, 0), "Fra brukskonto"),
+            ),
+            on_account(
+                "1",
+                TransactionObj::fixture("2018-01-10", Decimal::new(-250, 0), "Rema 1000"),
+            ),
+        ];
+
+        assert_eq!(remove_internal_transfers(&mut rows), 1);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].transaction.text, "Rema 1000");
+    }
+
+    #[test]
+    fn removes_transfers_that_name_the_other_account() {
+        let mut to_savings =
+            TransactionObj::fixture("2018-01-10", Decimal::new(-2000, 0), "Sparing");
+        to_savings.other_account_number_specified = true;
+        to_savings.other_account_number = Some("2".to_string());
+
+        let mut rows = vec![
+            on_account("1", to_savings),
+            on_account(
+                "2",
+                TransactionObj::fixture("2018-01-10", Decimal::new(2000, 0), "Sparing"),
+            ),
+        ];
+
+        assert_eq!(remove_internal_transfers(&mut rows), 1);
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn keeps_unrelated_transactions_of_the_same_amount() {
+        let mut rows = vec![
+            on_account(
+                "1",
+                TransactionObj::fixture("2018-01-10", Decimal::new(-500, 0), "Elkjøp")
+                    .with_card("*1234", "Elkjøp", "4321"),
+            ),
+            on_account(
+                "2",
+                TransactionObj::fixture("2018-01-10", Decimal::new(500, 0), "Vipps refusjon"),
+            ),
+        ];
+
+        assert_eq!(remove_internal_transfers(&mut rows), 0);
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn keeps_transfers_that_only_look_internal() {
+        let mut to_someone_else = transfer("2018-01-10", Decimal::new(-2000, 0), "Til Ola");
+        to_someone_else.other_account_number_specified = true;
+        to_someone_else.other_account_number = Some("3".to_string());
+
+        let mut rows = vec![
+            on_account("1", to_someone_else),
+            on_account(
+                "2",
+                transfer("2018-01-10", Decimal::new(2000, 0), "Fra Kari"),
+            ),
+            // Another day, or within one account, is not a transfer.
+            on_account(
+                "1",
+                transfer("2018-01-11", Decimal::new(-500, 0), "Til sparing"),
+            ),
+            on_account(
+                "2",
+                transfer("2018-01-12", Decimal::new(500, 0), "Fra brukskonto"),
+            ),
+            on_account("1", transfer("2018-01-13", Decimal::new(-100, 0), "Kiwi")),
+            on_account(
+                "1",
+                transfer("2018-01-13", Decimal::new(100, 0), "Kiwi refund"),
+            ),
+        ];
+
+        assert_eq!(remove_internal_transfers(&mut rows), 0);
+        assert_eq!(rows.len(), 6);
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use core::entities::{date, AccountObj, TransactionObj};
+use core::error::Error;
+use core::store::Store;
+use rust_decimal::Decimal;
+use std::fmt;
+use time::Duration;
+
+/// How long after a reservation its booked transaction is looked for when
+/// the two can only be matched on the text.
+const SETTLE_DAYS: i64 = 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReservationStatus {
+    /// Booked for the reserved amount.
+    Settled,
+    /// Booked for another amount than reserved, e.g. a fuel or hotel
+    /// pre-authorization.
+    SettledDifferentAmount,
+    /// No longer listed, with no booked transaction to match it.
+    Removed,
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReservationStatus::Settled => write!(f, "settled"),
+            ReservationStatus::SettledDifferentAmount => write!(f, "settled, amount changed"),
+            ReservationStatus::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+/// A reservation that has disappeared from the account since it was
+/// first synced.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationChange {
+    pub account_name: String,
+    pub status: ReservationStatus,
+    pub text: String,
+    pub reserved_date: String,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub reserved_amount: Decimal,
+    pub booked_date: Option<String>,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision_option::serialize")]
+    pub booked_amount: Option<Decimal>,
+    /// Booked less reserved amount.
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision_option::serialize")]
+    pub difference: Option<Decimal>,
+    /// When a sync first found the reservation gone.
+    pub noticed_at: String,
+}
+
+impl fmt::Display for ReservationChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}: {}",
+            self.reserved_date, self.account_name, self.reserved_amount, self.text, self.status
+        )?;
+
+        if let (Some(booked_date), Some(booked_amount)) =
+            (self.booked_date.as_ref(), self.booked_amount)
+        {
+            write!(f, " {} on {}", booked_amount, booked_date)?;
+        }
+
+        if let Some(difference) = self.difference {
+            write!(f, " ({:+})", difference)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the reservations on the accounts, reserved in the date range,
+/// that later syncs no longer listed, and how each of them was settled.
+///
+/// A reservation is matched to a booked transaction with the same card
+/// transaction id, or else to one with the same text booked within
+/// `SETTLE_DAYS` of it. A booked transaction settles one reservation only.
+pub fn reservation_changes(
+    store: &Store,
+    accounts: &[&AccountObj],
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> Result<Vec<ReservationChange>, Error> {
+    let mut changes: Vec<ReservationChange> = Vec::new();
+
+    for account in accounts {
+        let reservations = store.removed_reservations(&account.account_id, start_date, end_date)?;
+        let mut booked: Vec<Option<TransactionObj>> = store
+            .booked_transactions(&account.account_id, start_date)?
+            .into_iter()
+            .map(Some)
+            .collect();
+
+        for (reservation, noticed_at) in reservations {
+            let index = booked
+                .iter()
+                .position(|b| {
+                    b.as_ref()
+                        .map_or(false, |b| same_card_purchase(&reservation, b))
+                })
+                .or_else(|| {
+                    booked
+                        .iter()
+                        .position(|b| b.as_ref().map_or(false, |b| same_text(&reservation, b)))
+                });
+
+            let settled: Option<TransactionObj> = index.and_then(|index| booked[index].take());
+
+            let status = match settled {
+                Some(ref settled) if settled.amount == reservation.amount => {
+                    ReservationStatus::Settled
+                }
+                Some(_) => ReservationStatus::SettledDifferentAmount,
+                None => ReservationStatus::Removed,
+            };
+
+            changes.push(ReservationChange {
+                account_name: account.name.to_string(),
+                status,
+                text: reservation.text.to_string(),
+                reserved_date: date(&reservation.accounting_date).to_string(),
+                reserved_amount: reservation.amount,
+                booked_date: settled
+                    .as_ref()
+                    .map(|settled| date(&settled.accounting_date).to_string()),
+                booked_amount: settled.as_ref().map(|settled| settled.amount),
+                difference: settled
+                    .as_ref()
+                    .map(|settled| settled.amount - reservation.amount),
+                noticed_at,
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.reserved_date.cmp(&b.reserved_date));
+
+    Ok(changes)
+}
+
+fn same_card_purchase(reservation: &TransactionObj, booked: &TransactionObj) -> bool {
+    match (&reservation.card_details, &booked.card_details) {
+        (&Some(ref reserved), &Some(ref booked)) => {
+            !reserved.transaction_id.is_empty() && reserved.transaction_id == booked.transaction_id
+        }
+        _ => false,
+    }
+}
+
+fn same_text(reservation: &TransactionObj, booked: &TransactionObj) -> bool {
+    if reservation.text.trim().to_lowercase() != booked.text.trim().to_lowercase() {
+        return false;
+    }
+
+    let parse = |value: &str| NaiveDate::parse_from_str(date(value), "%Y-%m-%d").ok();
+
+    match (
+        parse(&reservation.accounting_date),
+        parse(&booked.accounting_date),
+    ) {
+        (Some(reserved), Some(booked)) => {
+            booked >= reserved && booked - reserved <= Duration::days(SETTLE_DAYS)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purchase(date: &str, amount: Decimal, card_transaction_id: &str) -> TransactionObj {
+        TransactionObj::fixture(date, amount, "Circle K").with_card(
+            "*1234",
+            "Circle K",
+            card_transaction_id,
+        )
+    }
+
+    #[test]
+    fn matches_card_purchases_on_the_card_transaction_id() {
+        let reserved = purchase("2018-09-20", Decimal::new(-1500, 0), "4321");
+        let booked = purchase("2018-09-22", Decimal::new(-68250, 2), "4321");
+        let other = purchase("2018-09-22", Decimal::new(-1500, 0), "9876");
+
+        assert!(same_card_purchase(&reserved, &booked));
+        assert!(!same_card_purchase(&reserved, &other));
+    }
+
+    #[test]
+    fn needs_a_card_transaction_id_to_match_on_it() {
+        let reserved = purchase("2018-09-20", Decimal::new(-1500, 0), "");
+        let booked = purchase("2018-09-22", Decimal::new(-1500, 0), "");
+        let without_card =
+            TransactionObj::fixture("2018-09-22", Decimal::new(-1500, 0), "Circle K");
+
+        assert!(!same_card_purchase(&reserved, &booked));
+        assert!(!same_card_purchase(&reserved, &without_card));
+    }
+
+    #[test]
+    fn matches_the_text_ignoring_case_and_spaces() {
+        let reserved = TransactionObj::fixture("2018-09-20", Decimal::new(-250, 0), "REMA 1000 ");
+        let booked = TransactionObj::fixture("2018-09-21", Decimal::new(-250, 0), "Rema 1000");
+        let other = TransactionObj::fixture("2018-09-21", Decimal::new(-250, 0), "Kiwi");
+
+        assert!(same_text(&reserved, &booked));
+        assert!(!same_text(&reserved, &other));
+    }
+
+    #[test]
+    fn matches_the_text_only_when_booked_within_the_settle_days() {
+        let reserved = TransactionObj::fixture("2018-09-20", Decimal::new(-250, 0), "Rema 1000");
+
+        let same_day = TransactionObj::fixture("2018-09-20", Decimal::new(-250, 0), "Rema 1000");
+        let last_day = TransactionObj::fixture("2018-10-04", Decimal::new(-250, 0), "Rema 1000");
+        let too_late = TransactionObj::fixture("2018-10-05", Decimal::new(-250, 0), "Rema 1000");
+        let before = TransactionObj::fixture("2018-09-19", Decimal::new(-250, 0), "Rema 1000");
+
+        assert!(same_text(&reserved, &same_day));
+        assert!(same_text(&reserved, &last_day));
+        assert!(!same_text(&reserved, &too_late));
+        assert!(!same_text(&reserved, &before));
+    }
+}
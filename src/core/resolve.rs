@@ -0,0 +1,73 @@
+use core::entities::AccountObj;
+use core::error::Error;
+use std::collections::HashMap;
+
+/// Maps what a user knows about an account to the account itself.
+///
+/// `input` is matched, in order, against the aliases from the config file,
+/// the account id, the account number (dots and spaces ignored), the
+/// account name and finally a unique prefix of the account name. Names are
+/// compared case-insensitively.
+pub fn resolve_account<'a>(
+    accounts: &'a [AccountObj],
+    aliases: &HashMap<String, String>,
+    input: &str,
+) -> Result<&'a AccountObj, Error> {
+    let input: &str = match aliases.get(input) {
+        Some(target) => target,
+        None => input,
+    };
+
+    if let Some(account) = accounts.iter().find(|a| a.account_id == input) {
+        return Ok(account);
+    }
+
+    let number = normalize_number(input);
+    if let Some(account) = accounts
+        .iter()
+        .find(|a| normalize_number(&a.account_number) == number)
+    {
+        return Ok(account);
+    }
+
+    let name = input.to_lowercase();
+
+    let exact: Vec<&AccountObj> = accounts
+        .iter()
+        .filter(|a| a.name.to_lowercase() == name)
+        .collect();
+    if !exact.is_empty() {
+        return single(input, exact);
+    }
+
+    let prefixed: Vec<&AccountObj> = accounts
+        .iter()
+        .filter(|a| a.name.to_lowercase().starts_with(&name))
+        .collect();
+    if !prefixed.is_empty() {
+        return single(input, prefixed);
+    }
+
+    Err(Error::UnknownAccount(input.to_string()))
+}
+
+fn single<'a>(input: &str, mut matches: Vec<&'a AccountObj>) -> Result<&'a AccountObj, Error> {
+    if matches.len() == 1 {
+        return Ok(matches.remove(0));
+    }
+
+    Err(Error::AmbiguousAccount {
+        input: input.to_string(),
+        candidates: matches
+            .iter()
+            .map(|a| format!("{} [nr: {}]", a.name, a.account_number))
+            .collect(),
+    })
+}
+
+fn normalize_number(number: &str) -> String {
+    number
+        .chars()
+        .filter(|c| *c != '.' && !c.is_whitespace())
+        .collect()
+}
@@ -0,0 +1,37 @@
+use core::entities::ErrorObj;
+use core::error::Error;
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+/// Reads the body of an API response as `T`.
+///
+/// Both the bank and customers APIs report failures through `isError` in
+/// the body, so that is checked before the body is parsed as `T`, as an
+/// error body usually lacks `item` or `items`.
+pub fn read_json<T: DeserializeOwned>(mut response: Response) -> Result<T, Error> {
+    let http_status: u16 = response.status().as_u16();
+    let body: String = response.text()?;
+
+    if let Ok(error) = serde_json::from_str::<ErrorObj>(&body) {
+        if error.is_error {
+            return Err(Error::Api {
+                error_type: error.error_type,
+                message: error.error_message,
+                trace_id: error.trace_id,
+                http_status,
+            });
+        }
+    }
+
+    if !response.status().is_success() {
+        return Err(Error::Api {
+            error_type: None,
+            message: None,
+            trace_id: None,
+            http_status,
+        });
+    }
+
+    Ok(serde_json::from_str::<T>(&body)?)
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Holds the client secret. The memory is wiped when the value is dropped,
+/// and `Debug` never prints the secret itself.
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Secret {
+        Secret(value)
+    }
+
+    /// Keeps only the first line of `value`, which is how `pass show` and
+    /// most other password managers print the secret.
+    pub fn from_first_line(mut value: String) -> Secret {
+        let end = value
+            .find(|c| c == '\n' || c == '\r')
+            .unwrap_or_else(|| value.len());
+        value.truncate(end);
+
+        Secret(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
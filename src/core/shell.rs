@@ -0,0 +1,211 @@
+use core::error::Error;
+use dirs;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::{self, Editor, Helper};
+use std::fs::DirBuilder;
+use std::mem;
+use std::path::PathBuf;
+
+const PROMPT: &str = "sbanken> ";
+
+/// Commands completed as the first word on a line.
+const COMMANDS: &[&str] = &[
+    "account",
+    "customer",
+    "transaction",
+    "transfer",
+    "template",
+    "sync",
+    "report",
+    "auth",
+    "help",
+    "exit",
+];
+
+/// Reads commands from the terminal until `exit` or Ctrl-D, handing each
+/// line to `execute` split into words. Account names and aliases in
+/// `accounts` are offered when completing options that take an account.
+///
+/// History is kept between sessions in the data directory.
+pub fn run<F: FnMut(Vec<String>)>(accounts: Vec<String>, mut execute: F) -> Result<(), Error> {
+    let mut editor = Editor::<ShellHelper>::new();
+    editor.set_helper(Some(ShellHelper { accounts }));
+
+    let history: Option<PathBuf> = history_path();
+    if let Some(ref path) = history {
+        // There is no history on the first run.
+        let _ = editor.load_history(path);
+    }
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(Error::Readline(err)),
+        };
+
+        let words: Vec<String> = match split_words(&line) {
+            Ok(words) => words,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+
+        if words.is_empty() {
+            continue;
+        }
+
+        editor.add_history_entry(line.as_str());
+
+        if words[0] == "exit" || words[0] == "quit" {
+            break;
+        }
+
+        execute(words);
+    }
+
+    if let Some(path) = history {
+        if let Some(dir) = path.parent() {
+            DirBuilder::new().recursive(true).create(dir)?;
+        }
+        editor.save_history(&path)?;
+    }
+
+    Ok(())
+}
+
+fn history_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("sbanken-cli").join("history"))
+}
+
+/// Splits a line into words the way a shell would, so names and messages
+/// with spaces can be given in quotes.
+fn split_words(line: &str) -> Result<Vec<String>, Error> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c == '\\' => {
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(mem::replace(&mut word, String::new()));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(Error::Parsable("unterminated quote"));
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Whether `option` of `command` is given an account.
+fn takes_account(command: &str, option: &str) -> bool {
+    match command {
+        "account" | "transaction" | "sync" | "report" => option == "-a" || option == "--account",
+        "transfer" | "template" => ["-f", "--from", "-t", "--to"].contains(&option),
+        _ => false,
+    }
+}
+
+struct ShellHelper {
+    accounts: Vec<String>,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+
+        // The word being completed starts after the last space outside
+        // quotes, so `--from "My sa` completes the whole account name.
+        let mut start = 0;
+        let mut quote: Option<char> = None;
+        for (i, c) in line.char_indices() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c.is_whitespace() => start = i + 1,
+                None => {}
+            }
+        }
+
+        let word = line[start..].trim_left_matches(|c| c == '"' || c == '\'');
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let command: Option<&str> = previous
+            .iter()
+            .find(|word| COMMANDS.contains(word))
+            .cloned();
+
+        let candidates: Vec<String> = match command {
+            None => COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(word))
+                .map(|command| command.to_string())
+                .collect(),
+            Some(command) => match previous.last() {
+                Some(option) if takes_account(command, option) => {
+                    let word = word.to_lowercase();
+                    self.accounts
+                        .iter()
+                        .filter(|account| account.to_lowercase().starts_with(&word))
+                        .map(|account| {
+                            if account.contains(char::is_whitespace) {
+                                format!("\"{}\"", account)
+                            } else {
+                                account.to_string()
+                            }
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            },
+        };
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper {
+    fn hint(&self, _line: &str, _pos: usize) -> Option<String> {
+        None
+    }
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Helper for ShellHelper {}
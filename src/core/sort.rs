@@ -0,0 +1,55 @@
+use core::entities::TransactionObj;
+use core::error::Error;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Date,
+    Amount,
+    Merchant,
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "date" => Ok(SortKey::Date),
+            "amount" => Ok(SortKey::Amount),
+            "merchant" => Ok(SortKey::Merchant),
+            _ => Err(Error::Parsable(
+                "sort must be one of date, amount or merchant",
+            )),
+        }
+    }
+}
+
+/// The merchant of a card purchase, or the text of any other transaction.
+pub fn merchant(transaction: &TransactionObj) -> &str {
+    match transaction.card_details {
+        Some(ref card) if !card.merchant_name.is_empty() => &card.merchant_name,
+        _ => &transaction.text,
+    }
+}
+
+/// Sorts oldest first, by the smallest amount, so the largest expenses
+/// come first, or by merchant from A to Z. `reverse` turns the order
+/// around. Transactions comparing equal keep their order.
+pub fn sort<T: AsRef<TransactionObj>>(rows: &mut [T], key: SortKey, reverse: bool) {
+    rows.sort_by(|a, b| {
+        let (a, b) = (a.as_ref(), b.as_ref());
+
+        let ordering: Ordering = match key {
+            SortKey::Date => a.accounting_date.cmp(&b.accounting_date),
+            SortKey::Amount => a.amount.cmp(&b.amount),
+            SortKey::Merchant => merchant(a).to_lowercase().cmp(&merchant(b).to_lowercase()),
+        };
+
+        if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
@@ -0,0 +1,578 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use core::bank::BankAPI;
+use core::entities::{
+    date, Account, AccountObj, AccountTransactionObj, AccountTransactions, Accounts, MultipleItems,
+    SingleItem, TransactionObj, Transactions,
+};
+use core::error::Error;
+use dirs;
+use rusqlite::{self, Connection};
+use serde_json;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{DirBuilder, OpenOptions};
+use std::path::PathBuf;
+use time::Duration;
+
+#[cfg(unix)]
+use std::fs::{set_permissions, Permissions};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+
+/// How far before the newest stored transaction a sync starts, so
+/// reservations that settled or disappeared since the last sync are seen.
+pub const OVERLAP_DAYS: i64 = 14;
+
+/// How far back the first sync of an account goes.
+pub const INITIAL_DAYS: i64 = 365;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS accounts (
+        account_id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS transactions (
+        account_id TEXT NOT NULL,
+        transaction_id TEXT NOT NULL,
+        date TEXT NOT NULL,
+        is_reservation INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        removed_at TEXT,
+        PRIMARY KEY (account_id, transaction_id)
+    );
+    CREATE INDEX IF NOT EXISTS transactions_date ON transactions (account_id, date);
+";
+
+/// Transactions synced from the API, kept in SQLite so they can be
+/// listed offline and compared between syncs.
+pub struct Store {
+    connection: Connection,
+}
+
+/// What a sync of one account changed in the store.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub account_name: String,
+    pub fetched: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: fetched {}, {} new, {} no longer listed",
+            self.account_name, self.fetched, self.added, self.removed
+        )
+    }
+}
+
+impl Store {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("sbanken-cli").join("transactions.sqlite"))
+    }
+
+    pub fn open() -> Result<Store, Error> {
+        let path = match Store::path() {
+            Some(path) => path,
+            None => return Err(Error::Config("couldn't find a data directory")),
+        };
+
+        if let Some(dir) = path.parent() {
+            let mut builder = DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            builder.mode(0o700);
+            builder.create(dir)?;
+        }
+
+        // The store holds the whole transaction history, so it is only
+        // readable by the user, like the token cache. SQLite gives its
+        // journal the same permissions as the database.
+        let mut options = OpenOptions::new();
+        options.write(true).create(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        options.open(&path)?;
+
+        #[cfg(unix)]
+        set_permissions(&path, Permissions::from_mode(0o600))?;
+
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Store { connection })
+    }
+
+    /// Replaces the stored accounts with `accounts`.
+    pub fn save_accounts(&mut self, accounts: &[AccountObj]) -> Result<(), Error> {
+        let tx = self.connection.transaction()?;
+
+        tx.execute("DELETE FROM accounts", params![])?;
+        for account in accounts {
+            tx.execute(
+                "INSERT INTO accounts (account_id, data) VALUES (?1, ?2)",
+                params![account.account_id, serde_json::to_string(account)?],
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// The accounts as of the last sync.
+    pub fn accounts(&self) -> Result<Accounts, Error> {
+        let mut statement = self.connection.prepare("SELECT data FROM accounts")?;
+        let rows = statement.query_map(params![], |row| row.get::<_, String>(0))?;
+
+        let mut accounts: Vec<AccountObj> = Vec::new();
+        for row in rows {
+            accounts.push(serde_json::from_str(&row?)?);
+        }
+
+        if accounts.is_empty() {
+            return Err(Error::NotSynced);
+        }
+
+        Ok(items(accounts))
+    }
+
+    /// One account as of the last sync, shaped like the API's answer.
+    pub fn account(&self, account_id: &str) -> Result<Account, Error> {
+        let data: String = match self.connection.query_row(
+            "SELECT data FROM accounts WHERE account_id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        ) {
+            Ok(data) => data,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(Error::UnknownAccount(account_id.to_string()))
+            }
+            Err(err) => return Err(Error::Sqlite(err)),
+        };
+
+        Ok(SingleItem {
+            item: serde_json::from_str(&data)?,
+            error_type: None,
+            error_message: None,
+            is_error: false,
+            trace_id: None,
+        })
+    }
+
+    /// Accounting date of the newest transaction stored for the account.
+    pub fn last_date(&self, account_id: &str) -> Result<Option<NaiveDate>, Error> {
+        let date: Option<String> = self.connection.query_row(
+            "SELECT MAX(date) FROM transactions WHERE account_id = ?1 AND removed_at IS NULL",
+            params![account_id],
+            |row| row.get(0),
+        )?;
+
+        match date {
+            Some(date) => Ok(Some(NaiveDate::parse_from_str(&date, "%Y-%m-%d")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the transactions fetched for the account from `start` on.
+    /// Transactions stored for that range which are no longer listed, such
+    /// as reservations that have settled, are marked as removed.
+    pub fn save_transactions(
+        &mut self,
+        account_id: &str,
+        start: NaiveDate,
+        transactions: &[TransactionObj],
+    ) -> Result<(usize, usize), Error> {
+        let now = Utc::now().to_rfc3339();
+        let start = start.format("%Y-%m-%d").to_string();
+
+        let tx = self.connection.transaction()?;
+
+        let stored: HashSet<String> = {
+            let mut statement = tx.prepare(
+                "SELECT transaction_id FROM transactions \
+                 WHERE account_id = ?1 AND date >= ?2 AND removed_at IS NULL",
+            )?;
+            let rows = statement.query_map(params![account_id, start], |row| row.get(0))?;
+
+            let mut stored = HashSet::new();
+            for row in rows {
+                stored.insert(row?);
+            }
+            stored
+        };
+
+        let mut listed: HashSet<String> = HashSet::new();
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        let mut added = 0;
+
+        for transaction in transactions {
+            // Rows without an id, such as two identical purchases on the
+            // same day, share a key, so each is numbered by how many of
+            // them came before it.
+            let key = {
+                let key = key(transaction);
+                let occurrence = occurrences.entry(key.to_string()).or_insert(0);
+                *occurrence += 1;
+
+                match *occurrence {
+                    1 => key,
+                    n => format!("{}#{}", key, n),
+                }
+            };
+
+            if !stored.contains(&key) {
+                added += 1;
+            }
+
+            tx.execute(
+                "INSERT INTO transactions \
+                 (account_id, transaction_id, date, is_reservation, data, first_seen, last_seen) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6) \
+                 ON CONFLICT (account_id, transaction_id) DO UPDATE SET \
+                 date = excluded.date, is_reservation = excluded.is_reservation, \
+                 data = excluded.data, last_seen = excluded.last_seen, removed_at = NULL",
+                params![
+                    account_id,
+                    key,
+                    date(&transaction.accounting_date),
+                    transaction.is_reservation,
+                    serde_json::to_string(transaction)?,
+                    now
+                ],
+            )?;
+
+            listed.insert(key);
+        }
+
+        let mut removed = 0;
+        for key in stored.difference(&listed) {
+            tx.execute(
+                "UPDATE transactions SET removed_at = ?3 \
+                 WHERE account_id = ?1 AND transaction_id = ?2",
+                params![account_id, key, now],
+            )?;
+            removed += 1;
+        }
+
+        tx.commit()?;
+
+        Ok((added, removed))
+    }
+
+    /// The stored transactions of the account in the date range, newest
+    /// first, leaving out those no longer listed by the API.
+    pub fn transactions(
+        &self,
+        account_id: &str,
+        length: Option<i32>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Transactions, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT data FROM transactions \
+             WHERE account_id = ?1 AND date >= ?2 AND date <= ?3 AND removed_at IS NULL \
+             ORDER BY date DESC LIMIT ?4",
+        )?;
+
+        let rows = statement.query_map(
+            params![
+                account_id,
+                start_date.format("%Y-%m-%d").to_string(),
+                end_date.format("%Y-%m-%d").to_string(),
+                // A negative limit means no limit to SQLite.
+                length.unwrap_or(-1)
+            ],
+            |row| row.get::<_, String>(0),
+        )?;
+
+        let mut transactions: Vec<TransactionObj> = Vec::new();
+        for row in rows {
+            transactions.push(serde_json::from_str(&row?)?);
+        }
+
+        Ok(items(transactions))
+    }
+
+    /// Reservations in the date range that a later sync no longer listed,
+    /// each with the time that was noticed.
+    pub fn removed_reservations(
+        &self,
+        account_id: &str,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<(TransactionObj, String)>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT data, removed_at FROM transactions \
+             WHERE account_id = ?1 AND date >= ?2 AND date <= ?3 \
+             AND is_reservation = 1 AND removed_at IS NOT NULL \
+             ORDER BY date",
+        )?;
+
+        let rows = statement.query_map(
+            params![
+                account_id,
+                start_date.format("%Y-%m-%d").to_string(),
+                end_date.format("%Y-%m-%d").to_string()
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?;
+
+        let mut reservations = Vec::new();
+        for row in rows {
+            let (data, removed_at) = row?;
+            reservations.push((serde_json::from_str(&data)?, removed_at));
+        }
+
+        Ok(reservations)
+    }
+
+    /// Booked transactions, as opposed to reservations, from `start_date` on.
+    pub fn booked_transactions(
+        &self,
+        account_id: &str,
+        start_date: DateTime<Utc>,
+    ) -> Result<Vec<TransactionObj>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT data FROM transactions \
+             WHERE account_id = ?1 AND date >= ?2 AND is_reservation = 0 AND removed_at IS NULL \
+             ORDER BY date",
+        )?;
+
+        let rows = statement.query_map(
+            params![account_id, start_date.format("%Y-%m-%d").to_string()],
+            |row| row.get::<_, String>(0),
+        )?;
+
+        let mut transactions = Vec::new();
+        for row in rows {
+            transactions.push(serde_json::from_str(&row?)?);
+        }
+
+        Ok(transactions)
+    }
+
+    /// The stored transactions of every account, merged newest first as
+    /// `BankAPI::get_transactions_for_accounts` does.
+    pub fn account_transactions(
+        &self,
+        accounts: &[AccountObj],
+        length: Option<i32>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<AccountTransactions, Error> {
+        let mut rows: Vec<AccountTransactionObj> = Vec::new();
+
+        for account in accounts {
+            let transactions =
+                self.transactions(&account.account_id, length, start_date, end_date)?;
+
+            rows.extend(
+                transactions
+                    .items
+                    .into_iter()
+                    .map(|transaction| AccountTransactionObj {
+                        account_name: account.name.to_string(),
+                        account_number: account.account_number.to_string(),
+                        transaction,
+                    }),
+            );
+        }
+
+        rows.sort_by(|a, b| {
+            b.transaction
+                .accounting_date
+                .cmp(&a.transaction.accounting_date)
+        });
+
+        Ok(items(rows))
+    }
+}
+
+/// Fetches the account's transactions since the last sync, less an overlap
+/// of `OVERLAP_DAYS`, and stores them. Accounts never synced before are
+/// fetched from `from`, or `INITIAL_DAYS` back.
+pub fn sync_account(
+    bank_api: &BankAPI,
+    store: &mut Store,
+    account: &AccountObj,
+    from: Option<NaiveDate>,
+) -> Result<SyncReport, Error> {
+    let end_date = Utc::now();
+
+    let start: NaiveDate = match store.last_date(&account.account_id)? {
+        Some(last) => last - Duration::days(OVERLAP_DAYS),
+        None => match from {
+            Some(from) => from,
+            None => (end_date - Duration::days(INITIAL_DAYS)).naive_utc().date(),
+        },
+    };
+
+    let start_date: DateTime<Utc> = DateTime::from_utc(start.and_hms(0, 0, 0), Utc);
+
+    let transactions =
+        bank_api.get_all_transactions(&account.account_id, start_date, end_date, |_, _| {})?;
+
+    let (added, removed) =
+        store.save_transactions(&account.account_id, start, &transactions.items)?;
+
+    Ok(SyncReport {
+        account_name: account.name.to_string(),
+        fetched: transactions.items.len(),
+        added,
+        removed,
+    })
+}
+
+/// What a transaction is stored under. Reservations aren't always given a
+/// transaction id, so those fall back to the id of the card purchase, or
+/// to the fields that identify them. The booking of a card purchase can
+/// have the same card purchase id as its reservation, so reservations are
+/// keyed apart from booked transactions.
+pub fn key(transaction: &TransactionObj) -> String {
+    if transaction.is_reservation {
+        format!("reserved:{}", id(transaction))
+    } else {
+        id(transaction)
+    }
+}
+
+fn id(transaction: &TransactionObj) -> String {
+    if !transaction.transaction_id.is_empty() && transaction.transaction_id != "0" {
+        return transaction.transaction_id.to_string();
+    }
+
+    if let Some(ref card) = transaction.card_details {
+        if !card.transaction_id.is_empty() {
+            return format!("card:{}", card.transaction_id);
+        }
+    }
+
+    format!(
+        "{}|{}|{}",
+        transaction.accounting_date, transaction.amount, transaction.text
+    )
+}
+
+fn items<T>(items: Vec<T>) -> MultipleItems<T> {
+    MultipleItems {
+        available_items: items.len() as i32,
+        items,
+        error_type: None,
+        is_error: false,
+        error_message: None,
+        trace_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+
+    fn store() -> Store {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+        Store { connection }
+    }
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn stored(store: &Store) -> Vec<TransactionObj> {
+        store
+            .transactions(
+                "1",
+                None,
+                Utc.ymd(2018, 9, 1).and_hms(0, 0, 0),
+                Utc.ymd(2018, 9, 30).and_hms(0, 0, 0),
+            )
+            .unwrap()
+            .items
+    }
+
+    #[test]
+    fn keeps_a_reservation_apart_from_its_booking() {
+        let mut store = store();
+
+        let reservation = TransactionObj::fixture("2018-09-20", Decimal::new(-250, 0), "Kiwi")
+            .with_card("*1234", "Kiwi", "4321")
+            .reserved();
+        let booking = TransactionObj::fixture("2018-09-21", Decimal::new(-250, 0), "Kiwi")
+            .with_card("*1234", "Kiwi", "4321");
+
+        assert_ne!(key(&reservation), key(&booking));
+
+        assert_eq!(
+            store
+                .save_transactions("1", day("2018-09-01"), &[reservation])
+                .unwrap(),
+            (1, 0)
+        );
+        assert_eq!(
+            store
+                .save_transactions("1", day("2018-09-01"), &[booking])
+                .unwrap(),
+            (1, 1)
+        );
+
+        let removed = store
+            .removed_reservations(
+                "1",
+                Utc.ymd(2018, 9, 1).and_hms(0, 0, 0),
+                Utc.ymd(2018, 9, 30).and_hms(0, 0, 0),
+            )
+            .unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].0.is_reservation);
+
+        let booked = store
+            .booked_transactions("1", Utc.ymd(2018, 9, 1).and_hms(0, 0, 0))
+            .unwrap();
+        assert_eq!(booked.len(), 1);
+        assert_eq!(booked[0].accounting_date, "2018-09-21T00:00:00");
+    }
+
+    #[test]
+    fn keeps_identical_purchases_on_the_same_day() {
+        let mut store = store();
+
+        let purchases = vec![
+            TransactionObj::fixture("2018-09-20", Decimal::new(-45, 0), "Narvesen"),
+            TransactionObj::fixture("2018-09-20", Decimal::new(-45, 0), "Narvesen"),
+        ];
+
+        assert_eq!(
+            store
+                .save_transactions("1", day("2018-09-01"), &purchases)
+                .unwrap(),
+            (2, 0)
+        );
+        assert_eq!(stored(&store).len(), 2);
+
+        // Syncing the same rows again neither adds nor removes any.
+        assert_eq!(
+            store
+                .save_transactions("1", day("2018-09-01"), &purchases)
+                .unwrap(),
+            (0, 0)
+        );
+        assert_eq!(stored(&store).len(), 2);
+
+        // One of them no longer listed leaves the other.
+        assert_eq!(
+            store
+                .save_transactions("1", day("2018-09-01"), &purchases[..1])
+                .unwrap(),
+            (0, 1)
+        );
+        assert_eq!(stored(&store).len(), 1);
+    }
+}
@@ -0,0 +1,100 @@
+use core::error::Error;
+use core::transfer::{validate_message, ValidationError};
+use dirs;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{DirBuilder, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use toml;
+
+/// A transfer saved under a name, e.g. a monthly top-up of a savings
+/// account. `from` and `to` take anything `resolve_account` does.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Template {
+    pub from: String,
+    pub to: String,
+    /// Used unless another amount is given when running the template.
+    pub amount: Decimal,
+    pub message: String,
+}
+
+impl Template {
+    /// Checks what can be checked without the accounts' balances, so a
+    /// template bound to fail is never saved.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.amount <= Decimal::new(0, 0) {
+            return Err(Error::InvalidTransfer(ValidationError::AmountNotPositive));
+        }
+
+        validate_message(&self.message).map_err(Error::InvalidTransfer)
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} to {} \"{}\"",
+            self.amount, self.from, self.to, self.message
+        )
+    }
+}
+
+/// The saved templates, kept in a file of their own next to the config
+/// file so adding one never rewrites the config by hand.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Templates {
+    #[serde(default)]
+    pub templates: BTreeMap<String, Template>,
+}
+
+impl Templates {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("sbanken-cli").join("templates.toml"))
+    }
+
+    pub fn load() -> Result<Templates, Error> {
+        let path = match Templates::path() {
+            Some(path) => path,
+            None => return Ok(Templates::default()),
+        };
+
+        if !path.exists() {
+            return Ok(Templates::default());
+        }
+
+        let mut content = String::new();
+        File::open(&path)?.read_to_string(&mut content)?;
+
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match Templates::path() {
+            Some(path) => path,
+            None => return Err(Error::Config("couldn't find a config directory")),
+        };
+
+        let content = match toml::to_string(self) {
+            Ok(content) => content,
+            Err(_) => return Err(Error::Message("couldn't serialize the templates")),
+        };
+
+        if let Some(dir) = path.parent() {
+            DirBuilder::new().recursive(true).create(dir)?;
+        }
+
+        File::create(&path)?.write_all(content.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Template, Error> {
+        match self.templates.get(name) {
+            Some(template) => Ok(template),
+            None => Err(Error::UnknownTemplate(name.to_string())),
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use core::entities::{AccountObj, TransferRequest};
+use core::error::Error;
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// The longest message the transfers API accepts.
+pub const MESSAGE_MAX_LENGTH: usize = 30;
+
+// Letters besides a to z allowed in a transfer message.
+const MESSAGE_LETTERS: &str = "æøåÆØÅ";
+
+// Characters besides letters and digits allowed in a transfer message.
+const MESSAGE_PUNCTUATION: &str = " .,:;-_/()?!+&'";
+
+/// Why a transfer was refused before being sent.
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    AmountNotPositive,
+    TooManyDecimals,
+    InsufficientFunds {
+        amount: Decimal,
+        disposable: Decimal,
+    },
+    SameAccount,
+    EmptyMessage,
+    MessageTooLong(usize),
+    InvalidCharacter(char),
+    /// The number of rows in a batch that failed validation.
+    InvalidBatchRows(usize),
+}
+
+impl ValidationError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            ValidationError::AmountNotPositive => "the amount must be positive",
+            ValidationError::TooManyDecimals => "the amount can't have more than two decimals",
+            ValidationError::InsufficientFunds { .. } => {
+                "the amount exceeds what is available on the from account"
+            }
+            ValidationError::SameAccount => "the from and to accounts must differ",
+            ValidationError::EmptyMessage => "the message can't be empty",
+            ValidationError::MessageTooLong(_) => "the message is too long",
+            ValidationError::InvalidCharacter(_) => "the message contains an invalid character",
+            ValidationError::InvalidBatchRows(_) => "the batch has invalid rows",
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::InsufficientFunds { amount, disposable } => write!(
+                f,
+                "the amount {} exceeds the {} available on the from account, credit included",
+                amount, disposable
+            ),
+            ValidationError::MessageTooLong(length) => write!(
+                f,
+                "the message is {} characters, at most {} are allowed",
+                length, MESSAGE_MAX_LENGTH
+            ),
+            ValidationError::InvalidCharacter(c) => {
+                write!(f, "the message can't contain `{}`", c)
+            }
+            ValidationError::InvalidBatchRows(rows) => write!(
+                f,
+                "{} rows of the batch are invalid, no transfers were made",
+                rows
+            ),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+/// Checks a transfer against the from account and what the transfers API
+/// accepts, so a transfer bound to fail is never sent.
+pub fn validate(accounts: &[AccountObj], request: &TransferRequest) -> Result<(), Error> {
+    let from = find(accounts, &request.from_account_id)?;
+    find(accounts, &request.to_account_id)?;
+
+    if request.amount <= Decimal::new(0, 0) {
+        return Err(Error::InvalidTransfer(ValidationError::AmountNotPositive));
+    }
+
+    if request.amount.normalize().scale() > 2 {
+        return Err(Error::InvalidTransfer(ValidationError::TooManyDecimals));
+    }
+
+    let disposable = from.available + from.credit_limit;
+    if request.amount > disposable {
+        return Err(Error::InvalidTransfer(ValidationError::InsufficientFunds {
+            amount: request.amount,
+            disposable,
+        }));
+    }
+
+    if request.from_account_id == request.to_account_id {
+        return Err(Error::InvalidTransfer(ValidationError::SameAccount));
+    }
+
+    validate_message(&request.message).map_err(Error::InvalidTransfer)
+}
+
+pub fn validate_message(message: &str) -> Result<(), ValidationError> {
+    if message.trim().is_empty() {
+        return Err(ValidationError::EmptyMessage);
+    }
+
+    let length = message.chars().count();
+    if length > MESSAGE_MAX_LENGTH {
+        return Err(ValidationError::MessageTooLong(length));
+    }
+
+    match message.chars().find(|c| {
+        !c.is_ascii_alphanumeric()
+            && !MESSAGE_LETTERS.contains(*c)
+            && !MESSAGE_PUNCTUATION.contains(*c)
+    }) {
+        Some(c) => Err(ValidationError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+/// What a transfer will do, shown before it is sent.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSummary {
+    pub from_account_name: String,
+    pub from_account_number: String,
+    pub to_account_name: String,
+    pub to_account_number: String,
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub amount: Decimal,
+    pub message: String,
+    /// What is left available on the from account once the transfer is made.
+    #[serde(serialize_with = "rust_decimal::serde::arbitrary_precision::serialize")]
+    pub available_after: Decimal,
+}
+
+impl TransferSummary {
+    pub fn new(accounts: &[AccountObj], request: &TransferRequest) -> Result<Self, Error> {
+        let from = find(accounts, &request.from_account_id)?;
+        let to = find(accounts, &request.to_account_id)?;
+
+        Ok(TransferSummary {
+            from_account_name: from.name.to_string(),
+            from_account_number: from.account_number.to_string(),
+            to_account_name: to.name.to_string(),
+            to_account_number: to.account_number.to_string(),
+            amount: request.amount,
+            message: request.message.to_string(),
+            available_after: from.available - request.amount,
+        })
+    }
+}
+
+impl fmt::Display for TransferSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\
+             From:\t\t{} [nr: {}]\n\
+             To:\t\t{} [nr: {}]\n\
+             Amount:\t\t{}\n\
+             Message:\t{}\n\
+             Available after:\t{}",
+            self.from_account_name,
+            self.from_account_number,
+            self.to_account_name,
+            self.to_account_number,
+            self.amount,
+            self.message,
+            self.available_after
+        )
+    }
+}
+
+fn find<'a>(accounts: &'a [AccountObj], account_id: &str) -> Result<&'a AccountObj, Error> {
+    match accounts.iter().find(|a| a.account_id == account_id) {
+        Some(account) => Ok(account),
+        None => Err(Error::NoAccountFound(
+            "the account to transfer with is no longer listed",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(account_id: &str, available: Decimal, credit_limit: Decimal) -> AccountObj {
+        AccountObj {
+            account_id: account_id.to_string(),
+            account_number: format!("9710{}", account_id),
+            owner_customer_id: "12345678901".to_string(),
+            name: format!("Account {}", account_id),
+            account_type: "Standard account".to_string(),
+            available,
+            balance: available,
+            credit_limit,
+        }
+    }
+
+    fn accounts() -> Vec<AccountObj> {
+        vec![
+            account("1", Decimal::new(1000, 0), Decimal::new(0, 0)),
+            account("2", Decimal::new(50, 0), Decimal::new(500, 0)),
+        ]
+    }
+
+    fn request(from: &str, to: &str, amount: Decimal, message: &str) -> TransferRequest {
+        TransferRequest {
+            from_account_id: from.to_string(),
+            to_account_id: to.to_string(),
+            message: message.to_string(),
+            amount,
+        }
+    }
+
+    fn validation_error(result: Result<(), Error>) -> ValidationError {
+        match result {
+            Err(Error::InvalidTransfer(err)) => err,
+            other => panic!("expected an invalid transfer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_transfer() {
+        let transfer = request("1", "2", Decimal::new(25050, 2), "Savings");
+        assert!(validate(&accounts(), &transfer).is_ok());
+    }
+
+    #[test]
+    fn rejects_amounts_that_are_not_positive() {
+        for amount in &[Decimal::new(0, 0), Decimal::new(-100, 0)] {
+            let transfer = request("1", "2", *amount, "Savings");
+            assert_eq!(
+                validation_error(validate(&accounts(), &transfer)),
+                ValidationError::AmountNotPositive
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_fractions_of_an_ore() {
+        let transfer = request("1", "2", Decimal::new(1001, 3), "Savings");
+        assert_eq!(
+            validation_error(validate(&accounts(), &transfer)),
+            ValidationError::TooManyDecimals
+        );
+
+        // Trailing zeros are not counted as decimals.
+        let transfer = request("1", "2", Decimal::new(1000, 3), "Savings");
+        assert!(validate(&accounts(), &transfer).is_ok());
+    }
+
+    #[test]
+    fn rejects_more_than_is_available() {
+        let transfer = request("1", "2", Decimal::new(100001, 2), "Savings");
+        assert_eq!(
+            validation_error(validate(&accounts(), &transfer)),
+            ValidationError::InsufficientFunds {
+                amount: Decimal::new(100001, 2),
+                disposable: Decimal::new(1000, 0),
+            }
+        );
+    }
+
+    #[test]
+    fn counts_the_credit_limit_as_available() {
+        let transfer = request("2", "1", Decimal::new(550, 0), "Savings");
+        assert!(validate(&accounts(), &transfer).is_ok());
+
+        let transfer = request("2", "1", Decimal::new(551, 0), "Savings");
+        assert_eq!(
+            validation_error(validate(&accounts(), &transfer)),
+            ValidationError::InsufficientFunds {
+                amount: Decimal::new(551, 0),
+                disposable: Decimal::new(550, 0),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_a_transfer_to_the_same_account() {
+        let transfer = request("1", "1", Decimal::new(100, 0), "Savings");
+        assert_eq!(
+            validation_error(validate(&accounts(), &transfer)),
+            ValidationError::SameAccount
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_accounts() {
+        let transfer = request("1", "3", Decimal::new(100, 0), "Savings");
+        match validate(&accounts(), &transfer) {
+            Err(Error::NoAccountFound(_)) => {}
+            other => panic!("expected no account found, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn allows_messages_of_thirty_characters() {
+        assert_eq!(validate_message(&"a".repeat(MESSAGE_MAX_LENGTH)), Ok(()));
+        assert_eq!(
+            validate_message(&"a".repeat(MESSAGE_MAX_LENGTH + 1)),
+            Err(ValidationError::MessageTooLong(31))
+        );
+    }
+
+    #[test]
+    fn counts_characters_rather_than_bytes() {
+        assert_eq!(validate_message(&"ø".repeat(MESSAGE_MAX_LENGTH)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_empty_messages() {
+        assert_eq!(validate_message(""), Err(ValidationError::EmptyMessage));
+        assert_eq!(validate_message("   "), Err(ValidationError::EmptyMessage));
+    }
+
+    #[test]
+    fn rejects_characters_the_api_refuses() {
+        assert_eq!(
+            validate_message("Rent: May (€), ok?"),
+            Err(ValidationError::InvalidCharacter('€'))
+        );
+        assert_eq!(
+            validate_message("me@home"),
+            Err(ValidationError::InvalidCharacter('@'))
+        );
+        assert_eq!(validate_message("Rent: May, Æ-Å/ok?"), Ok(()));
+    }
+
+    #[test]
+    fn rejects_letters_and_digits_outside_latin_and_norwegian() {
+        assert_eq!(
+            validate_message("Husleie 房租"),
+            Err(ValidationError::InvalidCharacter('房'))
+        );
+        assert_eq!(
+            validate_message("Faktura ١٢"),
+            Err(ValidationError::InvalidCharacter('١'))
+        );
+        assert_eq!(
+            validate_message("Café"),
+            Err(ValidationError::InvalidCharacter('é'))
+        );
+        assert_eq!(validate_message("Blåbær og øl 2018"), Ok(()));
+    }
+}
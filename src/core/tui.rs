@@ -0,0 +1,650 @@
+use chrono::Utc;
+use core::bank::BankAPI;
+use core::entities::{AccountObj, TransactionObj, TransferRequest};
+use core::error::Error;
+use core::money::parse_amount;
+use core::transfer::{validate, TransferSummary};
+use std::io::{self, Write};
+use std::mem;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::{clear, cursor, style, terminal_size};
+use time::Duration;
+
+const ACCOUNTS_WIDTH: u16 = 46;
+const HISTORY_DAYS: i64 = 30;
+const PAGE: usize = 10;
+
+const HELP: &str = " q quit  tab switch pane  / search  enter details  t transfer  r refresh";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Accounts,
+    Transactions,
+}
+
+enum Mode {
+    Browse,
+    Search,
+    Detail,
+    Transfer(TransferForm),
+    /// The summary of a filled in transfer, waiting for a yes. The form is
+    /// kept to go back to.
+    Confirm(TransferForm, TransferRequest, TransferSummary),
+}
+
+struct TransferForm {
+    to_account: usize,
+    field: usize,
+    amount: String,
+    message: String,
+}
+
+/// A full-screen dashboard with the accounts on the left and the
+/// transactions of the selected account on the right.
+pub struct Dashboard<'a> {
+    bank_api: &'a BankAPI<'a>,
+    accounts: Vec<AccountObj>,
+    selected_account: usize,
+    transactions: Vec<TransactionObj>,
+    selected_transaction: usize,
+    focus: Focus,
+    mode: Mode,
+    search: String,
+    status: String,
+}
+
+impl<'a> Dashboard<'a> {
+    pub fn new(bank_api: &'a BankAPI<'a>) -> Result<Dashboard<'a>, Error> {
+        let mut dashboard = Dashboard {
+            bank_api,
+            accounts: bank_api.get_accounts()?.items,
+            selected_account: 0,
+            transactions: Vec::new(),
+            selected_transaction: 0,
+            focus: Focus::Accounts,
+            mode: Mode::Browse,
+            search: String::new(),
+            status: String::new(),
+        };
+
+        dashboard.load_transactions()?;
+
+        Ok(dashboard)
+    }
+
+    pub fn run(&mut self) -> Result<(), Error> {
+        let stdin = io::stdin();
+        let mut screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+
+        write!(screen, "{}", cursor::Hide)?;
+        self.draw(&mut screen)?;
+
+        for key in stdin.keys() {
+            if !self.handle(key?) {
+                break;
+            }
+            self.draw(&mut screen)?;
+        }
+
+        write!(screen, "{}", cursor::Show)?;
+        screen.flush()?;
+
+        Ok(())
+    }
+
+    fn load_transactions(&mut self) -> Result<(), Error> {
+        self.transactions.clear();
+        self.selected_transaction = 0;
+
+        let account_id = match self.accounts.get(self.selected_account) {
+            Some(account) => account.account_id.to_string(),
+            None => return Ok(()),
+        };
+
+        let end_date = Utc::now();
+        let start_date = end_date - Duration::days(HISTORY_DAYS);
+
+        self.transactions = self
+            .bank_api
+            .get_all_transactions(&account_id, start_date, end_date, |_, _| {})?
+            .items;
+
+        Ok(())
+    }
+
+    fn refresh(&mut self) -> Result<(), Error> {
+        self.accounts = self.bank_api.get_accounts()?.items;
+
+        if self.selected_account >= self.accounts.len() {
+            self.selected_account = 0;
+        }
+
+        self.load_transactions()
+    }
+
+    /// Indexes of the transactions matching the search, on the text or the
+    /// merchant name.
+    fn filtered(&self) -> Vec<usize> {
+        let search = self.search.to_lowercase();
+
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|&(_, transaction)| {
+                search.is_empty()
+                    || transaction.text.to_lowercase().contains(&search)
+                    || transaction
+                        .card_details
+                        .as_ref()
+                        .map(|card| card.merchant_name.to_lowercase().contains(&search))
+                        .unwrap_or(false)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&TransactionObj> {
+        self.filtered()
+            .get(self.selected_transaction)
+            .map(|&i| &self.transactions[i])
+    }
+
+    /// Handles a key press, returns false once the user quits.
+    fn handle(&mut self, key: Key) -> bool {
+        let result = match self.mode {
+            Mode::Browse => return self.handle_browse(key),
+            Mode::Search => {
+                self.handle_search(key);
+                Ok(())
+            }
+            Mode::Detail => {
+                if let Key::Esc | Key::Char('\n') | Key::Char('q') = key {
+                    self.mode = Mode::Browse;
+                }
+                Ok(())
+            }
+            Mode::Transfer(_) => self.handle_transfer(key),
+            Mode::Confirm(..) => self.handle_confirm(key),
+        };
+
+        if let Err(err) = result {
+            self.status = err.to_string().replace('\n', " ");
+        }
+
+        true
+    }
+
+    fn handle_browse(&mut self, key: Key) -> bool {
+        self.status.clear();
+
+        let result = match key {
+            Key::Char('q') | Key::Ctrl('c') => return false,
+            Key::Char('\t') | Key::Left | Key::Right | Key::Char('h') | Key::Char('l') => {
+                self.focus = match self.focus {
+                    Focus::Accounts => Focus::Transactions,
+                    Focus::Transactions => Focus::Accounts,
+                };
+                Ok(())
+            }
+            Key::Up | Key::Char('k') => self.move_selection(-1),
+            Key::Down | Key::Char('j') => self.move_selection(1),
+            Key::PageUp => self.move_selection(-(PAGE as i32)),
+            Key::PageDown => self.move_selection(PAGE as i32),
+            Key::Char('/') => {
+                self.mode = Mode::Search;
+                Ok(())
+            }
+            Key::Esc => {
+                self.search.clear();
+                self.selected_transaction = 0;
+                Ok(())
+            }
+            Key::Char('\n') => {
+                if self.selected().is_some() {
+                    self.mode = Mode::Detail;
+                }
+                Ok(())
+            }
+            Key::Char('t') => {
+                if self.accounts.len() < 2 {
+                    self.status = "Transfers need at least two accounts".to_string();
+                } else {
+                    self.mode = Mode::Transfer(TransferForm {
+                        to_account: if self.selected_account == 0 { 1 } else { 0 },
+                        field: 0,
+                        amount: String::new(),
+                        message: String::new(),
+                    });
+                }
+                Ok(())
+            }
+            Key::Char('r') => self.refresh(),
+            _ => Ok(()),
+        };
+
+        if let Err(err) = result {
+            self.status = err.to_string().replace('\n', " ");
+        }
+
+        true
+    }
+
+    fn move_selection(&mut self, delta: i32) -> Result<(), Error> {
+        match self.focus {
+            Focus::Accounts => {
+                let selected = clamp(self.selected_account, delta, self.accounts.len());
+                if selected != self.selected_account {
+                    self.selected_account = selected;
+                    self.load_transactions()?;
+                }
+            }
+            Focus::Transactions => {
+                let count = self.filtered().len();
+                self.selected_transaction = clamp(self.selected_transaction, delta, count);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_search(&mut self, key: Key) {
+        match key {
+            Key::Char('\n') => self.mode = Mode::Browse,
+            Key::Esc => {
+                self.search.clear();
+                self.mode = Mode::Browse;
+            }
+            Key::Backspace => {
+                self.search.pop();
+            }
+            Key::Char(c) if !c.is_control() => self.search.push(c),
+            _ => {}
+        }
+
+        self.selected_transaction = 0;
+        self.focus = Focus::Transactions;
+    }
+
+    fn handle_transfer(&mut self, key: Key) -> Result<(), Error> {
+        let accounts = self.accounts.len();
+        let from_account = self.selected_account;
+        let form = match self.mode {
+            Mode::Transfer(ref mut form) => form,
+            _ => return Ok(()),
+        };
+
+        match key {
+            Key::Esc => {
+                self.mode = Mode::Browse;
+                return Ok(());
+            }
+            Key::Char('\t') | Key::Down => form.field = (form.field + 1) % 3,
+            Key::BackTab | Key::Up => form.field = (form.field + 2) % 3,
+            Key::Left | Key::Right if form.field == 0 => {
+                let step = if key == Key::Left { accounts - 1 } else { 1 };
+                form.to_account = (form.to_account + step) % accounts;
+                if form.to_account == from_account {
+                    form.to_account = (form.to_account + step) % accounts;
+                }
+            }
+            Key::Backspace => match form.field {
+                1 => {
+                    form.amount.pop();
+                }
+                2 => {
+                    form.message.pop();
+                }
+                _ => {}
+            },
+            Key::Char('\n') if form.field == 2 => return self.review_transfer(),
+            Key::Char('\n') => form.field += 1,
+            Key::Char(c) if !c.is_control() => match form.field {
+                1 => form.amount.push(c),
+                2 => form.message.push(c),
+                _ => {}
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Validates the filled in transfer and shows its summary for
+    /// confirmation. Nothing is sent until the user answers yes.
+    fn review_transfer(&mut self) -> Result<(), Error> {
+        let transfer = match self.mode {
+            Mode::Transfer(ref form) => TransferRequest {
+                from_account_id: self.accounts[self.selected_account].account_id.to_string(),
+                to_account_id: self.accounts[form.to_account].account_id.to_string(),
+                message: form.message.to_string(),
+                amount: parse_amount(&form.amount)?,
+            },
+            _ => return Ok(()),
+        };
+
+        validate(&self.accounts, &transfer)?;
+        let summary = TransferSummary::new(&self.accounts, &transfer)?;
+
+        self.status.clear();
+        self.mode = match mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Transfer(form) => Mode::Confirm(form, transfer, summary),
+            mode => mode,
+        };
+
+        Ok(())
+    }
+
+    fn handle_confirm(&mut self, key: Key) -> Result<(), Error> {
+        match key {
+            Key::Char('y') | Key::Char('Y') => self.submit_transfer(),
+            Key::Char('n') | Key::Char('N') | Key::Esc => {
+                self.mode = match mem::replace(&mut self.mode, Mode::Browse) {
+                    Mode::Confirm(form, _, _) => Mode::Transfer(form),
+                    mode => mode,
+                };
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn submit_transfer(&mut self) -> Result<(), Error> {
+        let (transfer, summary) = match mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Confirm(_, transfer, summary) => (transfer, summary),
+            mode => {
+                self.mode = mode;
+                return Ok(());
+            }
+        };
+
+        self.bank_api.post_transfer(&transfer)?;
+
+        self.status = format!(
+            "Transferred {} from {} to {}",
+            summary.amount, summary.from_account_name, summary.to_account_name
+        );
+
+        self.refresh()
+    }
+
+    fn draw<W: Write>(&self, screen: &mut W) -> Result<(), Error> {
+        let (width, height) = terminal_size()?;
+        let right = ACCOUNTS_WIDTH + 2;
+        let right_width = width.saturating_sub(right) as usize;
+        let rows = height.saturating_sub(3) as usize;
+
+        write!(screen, "{}", clear::All)?;
+
+        write!(
+            screen,
+            "{}{}{}{}",
+            cursor::Goto(1, 1),
+            style::Invert,
+            fit(HELP, width as usize),
+            style::Reset
+        )?;
+
+        // Accounts pane
+        write!(
+            screen,
+            "{}{}{:<22} {:>11} {:>11}{}",
+            cursor::Goto(1, 2),
+            style::Bold,
+            "Account",
+            "Balance",
+            "Available",
+            style::Reset
+        )?;
+
+        for (i, account) in self.accounts.iter().enumerate().take(rows) {
+            let line = format!(
+                "{} {:>11} {:>11}",
+                fit(&account.name, 22),
+                account.balance,
+                account.available
+            );
+            write!(screen, "{}", cursor::Goto(1, 3 + i as u16))?;
+            self.write_row(screen, &line, i == self.selected_account, Focus::Accounts)?;
+        }
+
+        // Transactions pane
+        match self.mode {
+            Mode::Detail => {
+                if let Some(transaction) = self.selected() {
+                    self.draw_lines(screen, right, right_width, &detail_lines(transaction))?;
+                }
+            }
+            Mode::Transfer(ref form) => {
+                let lines = self.transfer_lines(form);
+                self.draw_lines(screen, right, right_width, &lines)?;
+            }
+            Mode::Confirm(_, _, ref summary) => {
+                self.draw_lines(screen, right, right_width, &confirm_lines(summary))?;
+            }
+            _ => self.draw_transactions(screen, right, right_width, rows)?,
+        }
+
+        // Status line
+        let status = match self.mode {
+            Mode::Search => format!("/{}", self.search),
+            _ if !self.status.is_empty() => self.status.to_string(),
+            _ if !self.search.is_empty() => format!("Search: {} (esc to clear)", self.search),
+            _ => String::new(),
+        };
+        write!(
+            screen,
+            "{}{}",
+            cursor::Goto(1, height),
+            fit(&status, width as usize)
+        )?;
+
+        screen.flush()?;
+
+        Ok(())
+    }
+
+    fn draw_transactions<W: Write>(
+        &self,
+        screen: &mut W,
+        x: u16,
+        width: usize,
+        rows: usize,
+    ) -> Result<(), Error> {
+        let filtered = self.filtered();
+
+        write!(
+            screen,
+            "{}{}{}{}",
+            cursor::Goto(x, 2),
+            style::Bold,
+            fit(
+                &format!("{:<10} {:>12}  {}", "Date", "Amount", "Text"),
+                width
+            ),
+            style::Reset
+        )?;
+
+        let skip = if self.selected_transaction >= rows {
+            self.selected_transaction + 1 - rows
+        } else {
+            0
+        };
+
+        for (row, &i) in filtered.iter().enumerate().skip(skip).take(rows) {
+            let transaction = &self.transactions[i];
+            let line = format!(
+                "{:<10} {:>12} {}{}",
+                transaction
+                    .accounting_date
+                    .chars()
+                    .take(10)
+                    .collect::<String>(),
+                transaction.amount,
+                if transaction.is_reservation { "*" } else { " " },
+                transaction.text
+            );
+            write!(screen, "{}", cursor::Goto(x, 3 + (row - skip) as u16))?;
+            self.write_row(
+                screen,
+                &fit(&line, width),
+                row == self.selected_transaction,
+                Focus::Transactions,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_lines<W: Write>(
+        &self,
+        screen: &mut W,
+        x: u16,
+        width: usize,
+        lines: &[String],
+    ) -> Result<(), Error> {
+        for (i, line) in lines.iter().enumerate() {
+            write!(
+                screen,
+                "{}{}",
+                cursor::Goto(x, 2 + i as u16),
+                fit(line, width)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn write_row<W: Write>(
+        &self,
+        screen: &mut W,
+        line: &str,
+        selected: bool,
+        pane: Focus,
+    ) -> Result<(), Error> {
+        if selected && self.focus == pane {
+            write!(screen, "{}{}{}", style::Invert, line, style::Reset)?;
+        } else if selected {
+            write!(screen, "{}{}{}", style::Bold, line, style::Reset)?;
+        } else {
+            write!(screen, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    fn transfer_lines(&self, form: &TransferForm) -> Vec<String> {
+        let marker = |field: usize| if form.field == field { ">" } else { " " };
+        let from = &self.accounts[self.selected_account];
+
+        vec![
+            "Transfer (tab next field, enter review, esc cancel)".to_string(),
+            String::new(),
+            format!("  From:     {} [available: {}]", from.name, from.available),
+            format!(
+                "{} To:       < {} >",
+                marker(0),
+                self.accounts[form.to_account].name
+            ),
+            format!("{} Amount:   {}", marker(1), form.amount),
+            format!("{} Message:  {}", marker(2), form.message),
+        ]
+    }
+}
+
+fn confirm_lines(summary: &TransferSummary) -> Vec<String> {
+    vec![
+        "Make this transfer? (y transfer, n or esc go back)".to_string(),
+        String::new(),
+        format!(
+            "{:<18}{} [nr: {}]",
+            "From:", summary.from_account_name, summary.from_account_number
+        ),
+        format!(
+            "{:<18}{} [nr: {}]",
+            "To:", summary.to_account_name, summary.to_account_number
+        ),
+        format!("{:<18}{}", "Amount:", summary.amount),
+        format!("{:<18}{}", "Message:", summary.message),
+        format!("{:<18}{}", "Available after:", summary.available_after),
+    ]
+}
+
+fn detail_lines(transaction: &TransactionObj) -> Vec<String> {
+    let mut lines = vec![
+        format!("{:<22}{}", "Accounting date:", transaction.accounting_date),
+        format!("{:<22}{}", "Interest date:", transaction.interest_date),
+        format!("{:<22}{}", "Amount:", transaction.amount),
+        format!("{:<22}{}", "Description:", transaction.text),
+        format!("{:<22}{}", "Type:", transaction.transaction_type_text),
+        format!(
+            "{:<22}{}",
+            "Reserved:",
+            if transaction.is_reservation {
+                "Yes"
+            } else {
+                "No"
+            }
+        ),
+    ];
+
+    if let Some(ref other_account) = transaction.other_account_number {
+        lines.push(format!("{:<22}{}", "Other account number:", other_account));
+    }
+
+    lines.push(format!(
+        "{:<22}{}",
+        "Transaction id:", transaction.transaction_id
+    ));
+
+    if let Some(ref card) = transaction.card_details {
+        lines.push(String::new());
+        lines.push(format!("{:<22}{}", "Card number:", card.card_number));
+        lines.push(format!("{:<22}{}", "Merchant:", card.merchant_name));
+        lines.push(format!("{:<22}{}", "Merchant city:", card.merchant_city));
+        lines.push(format!(
+            "{:<22}{} {}",
+            "Merchant category:", card.merchant_category_code, card.merchant_category_description
+        ));
+        lines.push(format!(
+            "{:<22}{} {}",
+            "Original amount:", card.currency_amount, card.original_currency_code
+        ));
+        lines.push(format!("{:<22}{}", "Currency rate:", card.currency_rate));
+        lines.push(format!("{:<22}{}", "Purchase date:", card.purchase_date));
+    }
+
+    lines.push(String::new());
+    lines.push("esc to go back".to_string());
+
+    lines
+}
+
+fn clamp(index: usize, delta: i32, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    let index = index as i32 + delta;
+    if index < 0 {
+        0
+    } else if index as usize >= len {
+        len - 1
+    } else {
+        index as usize
+    }
+}
+
+/// Truncates or pads `text` to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let mut line: String = text.chars().take(width).collect();
+    let len = line.chars().count();
+
+    for _ in len..width {
+        line.push(' ');
+    }
+
+    line
+}
@@ -1,8 +1,14 @@
 extern crate chrono;
 extern crate clap;
+extern crate csv;
+extern crate dirs;
 extern crate hyper;
 extern crate regex;
 extern crate reqwest;
+#[macro_use]
+extern crate rusqlite;
+extern crate rust_decimal;
+extern crate rustyline;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
@@ -11,30 +17,101 @@ extern crate termion;
 #[macro_use]
 extern crate text_io;
 extern crate time;
+extern crate toml;
 extern crate url;
+extern crate zeroize;
 
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
-use clap::{App, Shell};
+use clap::{App, AppSettings, ArgMatches, Shell};
 use core::authorize::Authorize;
 use core::bank::BankAPI;
+use core::batch::{self, PreparedTransfer};
+use core::cache::TokenCache;
+use core::config::{parse_api_url, Config, NamedProfile, DEFAULT_API_URL};
 use core::credentials::Credentials;
 use core::customers::CustomersAPI;
-use core::entities::{AccountObj, Accounts, Transactions, TransferRequest};
-use core::error::Error;
-use core::interactive::{fuzzy_match_account, remove_account};
+use core::entities::{
+    Account, AccountObj, AccountTransactions, Accounts, Transactions, TransferRequest,
+};
+use core::error::{Error, EXIT_USAGE};
+use core::export::{
+    parse_columns, write_account_transactions, write_transactions, ExportFormat,
+    DEFAULT_ACCOUNT_COLUMNS, DEFAULT_COLUMNS,
+};
+use core::filter::Filter;
+use core::group::{group, GroupBy};
+use core::interactive::{
+    account_option, confirm, fuzzy_match_account, fuzzy_select, remove_account, Picker,
+};
+use core::money::parse_amount;
+use core::output::{print, print_error, print_items, print_json, print_list, OutputFormat};
+use core::report::{remove_internal_transfers, summarize, write_summary};
+use core::reservations::reservation_changes;
+use core::resolve::resolve_account;
+use core::shell;
+use core::sort::{sort, SortKey};
+use core::store::{sync_account, Store};
+use core::template::{Template, Templates};
+use core::transfer::{validate, TransferSummary, ValidationError};
+use core::tui::Dashboard;
+use regex::RegexBuilder;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::io;
+use std::process;
 use termion::{color, style};
 use time::Duration;
+use url::Url;
 
 mod cli;
 mod core;
 
-fn main() -> Result<(), Error> {
+fn main() {
     let app: App = cli::build_cli();
 
-    let matches = app.get_matches();
+    let matches = match app.get_matches_safe() {
+        Ok(matches) => matches,
+        Err(err) => {
+            if !err.use_stderr() {
+                // --help and --version
+                err.exit();
+            }
+            eprintln!("{}", err.message);
+            process::exit(EXIT_USAGE);
+        }
+    };
+
+    let output: OutputFormat = output_format(&matches);
+
+    if let Err(err) = run(&matches, output) {
+        print_error(&err, output);
+        process::exit(err.exit_code());
+    }
+}
+
+fn output_format(matches: &ArgMatches) -> OutputFormat {
+    match matches.value_of("output").unwrap_or("text").parse() {
+        Ok(output) => output,
+        Err(_) => OutputFormat::Text,
+    }
+}
 
+/// What every command needs, set up once per invocation or once for a
+/// whole `shell` session.
+struct Session<'a> {
+    credentials: &'a Credentials,
+    authorize: &'a Authorize<'a>,
+    bank_api: &'a BankAPI<'a>,
+    customer_api: &'a CustomersAPI<'a>,
+    aliases: HashMap<String, String>,
+    default_account: Option<String>,
+    picker: Option<Picker>,
+    color: bool,
+}
+
+fn run(matches: &ArgMatches, output: OutputFormat) -> Result<(), Error> {
     if matches.is_present("generate-bash-completions") {
         cli::build_cli().gen_completions_to("sbanken-cli", Shell::Bash, &mut std::io::stdout());
     }
@@ -55,7 +132,32 @@ fn main() -> Result<(), Error> {
         );
     }
 
-    let color_env: bool = match env::var("SBANKEN_COLOR") {
+    let config: Config = Config::load()?;
+
+    let profile_env: Option<String> = env::var("SBANKEN_PROFILE").ok();
+
+    let profile: Option<NamedProfile> = config.profile(
+        matches
+            .value_of("profile")
+            .or_else(|| profile_env.as_ref().map(|name| name.as_str())),
+    )?;
+
+    let color_env: Option<bool> = match env::var("SBANKEN_COLOR") {
+        Ok(value) => match value.as_ref() {
+            "1" => Some(true),
+            _ => Some(false),
+        },
+        Err(_) => None,
+    };
+
+    let color_profile: bool = match profile {
+        Some(ref profile) => profile.profile.color.unwrap_or(false),
+        None => false,
+    };
+
+    let color = color_env.unwrap_or(color_profile);
+
+    let cache_token_env: bool = match env::var("SBANKEN_CACHE_TOKEN") {
         Ok(value) => match value.as_ref() {
             "1" => true,
             _ => false,
@@ -63,73 +165,239 @@ fn main() -> Result<(), Error> {
         Err(_) => false,
     };
 
-    let color = color_env || matches.is_present("color");
+    let token_cache: Option<TokenCache> = if cache_token_env || matches.is_present("cache-token") {
+        TokenCache::new()
+    } else {
+        None
+    };
 
-    let secret: String = match env::var("SBANKEN_SECRET") {
-        Ok(secret) => secret,
-        Err(_) => {
-            return Err(Error::EnvMissing("SBANKEN_SECRET"));
-        }
+    let profile_api_url: Option<&String> = match profile {
+        Some(ref profile) => profile.profile.api_url.as_ref(),
+        None => None,
     };
 
-    let client_id: String = match env::var("SBANKEN_CLIENT_ID") {
-        Ok(client_id) => client_id,
-        Err(_) => {
-            return Err(Error::EnvMissing("SBANKEN_CLIENT_ID"));
-        }
+    let api_url: String = match matches.value_of("api-url") {
+        Some(api_url) => api_url.to_string(),
+        None => match env::var("SBANKEN_API_URL") {
+            Ok(api_url) => api_url,
+            Err(_) => match profile_api_url.or_else(|| config.api_url.as_ref()) {
+                Some(api_url) => api_url.to_string(),
+                None => DEFAULT_API_URL.to_string(),
+            },
+        },
     };
 
-    let customer_id: String = match env::var("SBANKEN_CUSTOMER_ID") {
-        Ok(customer_id) => customer_id,
-        Err(_) => {
-            return Err(Error::EnvMissing("SBANKEN_CUSTOMER_ID"));
-        }
+    let api_url: Url = parse_api_url(&api_url)?;
+
+    let credentials = &Credentials::load(profile.as_ref(), matches.is_present("secret-stdin"))?;
+
+    if matches.occurrences_of("v") > 0 {
+        eprintln!("client id read from {}", credentials.sources.client_id);
+        eprintln!("customer id read from {}", credentials.sources.customer_id);
+        eprintln!("secret read from {}", credentials.sources.secret);
+    }
+
+    let default_account: Option<String> = match profile {
+        Some(ref profile) => profile.profile.default_account.clone(),
+        None => None,
     };
 
-    let credentials = &Credentials::new(secret, client_id, customer_id);
+    let aliases: HashMap<String, String> = config.aliases(profile.as_ref());
+
+    let picker: Option<Picker> = match config.picker {
+        Some(ref picker) => Some(picker.parse()?),
+        None => None,
+    };
 
-    let authorize = &Authorize::new(credentials);
+    let authorize = &Authorize::new(credentials, api_url, token_cache);
 
     let bank_api = &BankAPI::new(authorize);
 
     let customer_api = &CustomersAPI::new(authorize);
 
+    let session = Session {
+        credentials,
+        authorize,
+        bank_api,
+        customer_api,
+        aliases,
+        default_account,
+        picker,
+        color,
+    };
+
+    if matches.subcommand_matches("shell").is_some() {
+        return run_shell(&session, output);
+    }
+
+    execute(matches, &session, output)
+}
+
+/// Keeps one session, and with it one access token, alive while running
+/// every command typed at the prompt.
+fn run_shell(session: &Session, output: OutputFormat) -> Result<(), Error> {
+    let accounts: Accounts = session.bank_api.get_accounts()?;
+
+    let mut completions: Vec<String> = session.aliases.keys().cloned().collect();
+    completions.extend(accounts.items.into_iter().map(|account| account.name));
+
+    shell::run(completions, |words| {
+        let matches = match cli::build_cli()
+            .setting(AppSettings::NoBinaryName)
+            .get_matches_from_safe(words)
+        {
+            Ok(matches) => matches,
+            // --help and --version are output, anything else an error.
+            Err(ref err) if !err.use_stderr() => {
+                println!("{}", err.message);
+                return;
+            }
+            Err(err) => {
+                print_error(&Error::Clap(err), output);
+                return;
+            }
+        };
+
+        let output: OutputFormat = if matches.occurrences_of("output") > 0 {
+            output_format(&matches)
+        } else {
+            output
+        };
+
+        if let Err(err) = execute(&matches, session, output) {
+            print_error(&err, output);
+        }
+    })
+}
+
+fn execute(matches: &ArgMatches, session: &Session, output: OutputFormat) -> Result<(), Error> {
+    let credentials = session.credentials;
+    let authorize = session.authorize;
+    let bank_api = session.bank_api;
+    let customer_api = session.customer_api;
+    let aliases = &session.aliases;
+    let default_account: Option<&String> = session.default_account.as_ref();
+    let picker: Option<Picker> = session.picker;
+    let color = session.color || matches.is_present("color");
+
+    if let Some(matches) = matches.subcommand_matches("auth") {
+        if matches.subcommand_matches("test").is_some() {
+            let token = match authorize.get_access_token() {
+                Ok(token) => token,
+                Err(ref err) if is_client_error(err) => {
+                    return Err(Error::Auth(
+                        "the identity server rejected the client id or secret",
+                    ))
+                }
+                Err(err) => {
+                    // Without a status the request never got an answer.
+                    let step = if err.status().is_none() && err.is_http() {
+                        "reach the identity server"
+                    } else {
+                        "request an access token"
+                    };
+                    return Err(Error::Step(step, Box::new(Error::Reqwest(err))));
+                }
+            };
+
+            let customer = match customer_api.get_customer() {
+                Ok(customer) => customer,
+                Err(Error::Api { http_status, .. }) if http_status < 500 => {
+                    return Err(Error::Auth("the customers API rejected the customer id"))
+                }
+                Err(err) => return Err(Error::Step("look up the customer", Box::new(err))),
+            };
+
+            if customer.item.customer_id != credentials.customer_id {
+                return Err(Error::Auth(
+                    "the customer returned doesn't match the configured customer id",
+                ));
+            }
+
+            let report = AuthReport {
+                steps: vec![
+                    AuthStep::ok("identity_server"),
+                    AuthStep::ok("access_token"),
+                    AuthStep::ok("customer"),
+                ],
+                token_type: token.token_type,
+                expires_at: (Utc::now() + Duration::seconds(i64::from(token.expires_in)))
+                    .to_rfc3339(),
+                expires_in: token.expires_in,
+                scope: token.scope.unwrap_or_default(),
+                customer_name: format!("{} {}", customer.item.first_name, customer.item.last_name),
+            };
+
+            print(&report, output)?;
+        }
+    }
+
+    // With --offline, accounts and transactions are read from the store
+    // filled by `sync` instead of the API.
+    let store: Option<Store> = match matches.subcommand() {
+        (_, Some(matches)) if matches.is_present("offline") => Some(Store::open()?),
+        _ => None,
+    };
+
+    let get_accounts = || match store {
+        Some(ref store) => store.accounts(),
+        None => bank_api.get_accounts(),
+    };
+
+    if let Some(matches) = matches.subcommand_matches("sync") {
+        let mut store = Store::open()?;
+
+        let from: Option<NaiveDate> = match matches.value_of("from") {
+            Some(from) => Some(NaiveDate::parse_from_str(from, "%Y-%m-%d")?),
+            None => None,
+        };
+
+        let accounts: Accounts = bank_api.get_accounts()?;
+        store.save_accounts(&accounts.items)?;
+
+        let accounts: Vec<&AccountObj> = match matches.value_of("account") {
+            Some(account) => vec![resolve_account(&accounts.items, aliases, account)?],
+            None => accounts.items.iter().collect(),
+        };
+
+        for account in accounts {
+            let report = sync_account(bank_api, &mut store, account, from)?;
+            print(&report, output)?;
+        }
+    }
+
     if let Some(matches) = matches.subcommand_matches("account") {
-        if let Some(account_number) = matches.value_of("account") {
-            let account = match bank_api.get_account(account_number) {
-                Ok(response) => response,
-                Err(err) => return Err(Error::Reqwest(err)),
+        if let Some(account) = matches.value_of("account") {
+            let accounts: Accounts = get_accounts()?;
+            let account = resolve_account(&accounts.items, aliases, account)?;
+
+            let account: Account = match store {
+                Some(ref store) => store.account(&account.account_id)?,
+                None => bank_api.get_account(&account.account_id)?,
             };
 
-            println!("{:}", account);
+            print(&account, output)?;
         } else {
-            let response: Accounts = match bank_api.get_accounts() {
-                Ok(response) => response,
-                Err(err) => return Err(Error::Reqwest(err)),
-            };
+            let response: Accounts = get_accounts()?;
 
             if matches.is_present("interactive") {
-                let account = fuzzy_match_account(&response.items, "Select account")?;
+                let account = fuzzy_match_account(&response.items, "Select account", picker)?;
 
-                println!("{:}", account);
-            }
-            if matches.is_present("list") {
+                print(account, output)?;
+            } else if matches.is_present("list") && output == OutputFormat::Text {
                 for account in response.items {
                     println!("{}\t\t[nr: {}]", account.name, account.account_number);
                 }
             } else {
-                println!("{:}", response);
+                print_items(&response, output)?;
             }
         }
     }
 
     if let Some(_matches) = matches.subcommand_matches("customer") {
-        let customer = match customer_api.get_customer() {
-            Ok(customer) => customer,
-            Err(err) => return Err(Error::Reqwest(err)),
-        };
+        let customer = customer_api.get_customer()?;
 
-        println!("{:}", customer);
+        print(&customer, output)?;
     }
 
     if let Some(matches) = matches.subcommand_matches("transaction") {
@@ -160,80 +428,458 @@ fn main() -> Result<(), Error> {
         };
 
         if end_date < start_date {
-            return Err(Error::Message("end_date was earlier than start date"));
+            return Err(Error::Parsable("end_date was earlier than start date"));
+        }
+
+        let size = |value: Option<&str>| -> Result<Option<Decimal>, Error> {
+            match value {
+                Some(value) => {
+                    let size = parse_amount(value)?;
+                    if size.is_sign_negative() {
+                        return Err(Error::Parsable(
+                            "--min and --max are compared with the amount without its sign",
+                        ));
+                    }
+                    Ok(Some(size))
+                }
+                None => Ok(None),
+            }
+        };
+
+        let filter = Filter {
+            grep: match matches.value_of("grep") {
+                Some(pattern) => Some(RegexBuilder::new(pattern).case_insensitive(true).build()?),
+                None => None,
+            },
+            min: size(matches.value_of("min"))?,
+            max: size(matches.value_of("max"))?,
+            transaction_type: matches.value_of("type").map(|t| t.to_string()),
+            credits: matches.is_present("credits"),
+            debits: matches.is_present("debits"),
+            exclude_reservations: matches.is_present("exclude-reservations"),
+            card: matches.value_of("card").map(|card| card.to_string()),
+        };
+
+        if matches.is_present("reservation-changes") {
+            let accounts: Accounts = get_accounts()?;
+
+            let accounts: Vec<&AccountObj> = if matches.is_present("all-accounts") {
+                accounts.items.iter().collect()
+            } else {
+                match matches
+                    .value_of("account")
+                    .or_else(|| default_account.map(|a| a.as_str()))
+                {
+                    Some(account) => vec![resolve_account(&accounts.items, aliases, account)?],
+                    None => {
+                        return Err(Error::ArgumentMissing(
+                            "account, give --account or set default_account in your profile",
+                        ));
+                    }
+                }
+            };
+
+            // Changes are found by comparing syncs, so sync first unless
+            // asked to stay offline.
+            let changes = match store {
+                Some(ref store) => reservation_changes(store, &accounts, start_date, end_date)?,
+                None => {
+                    let mut store = Store::open()?;
+                    for account in &accounts {
+                        sync_account(bank_api, &mut store, account, None)?;
+                    }
+                    reservation_changes(&store, &accounts, start_date, end_date)?
+                }
+            };
+
+            return print_list(&changes, output);
+        }
+
+        if matches.is_present("all-accounts") {
+            let accounts: Accounts = get_accounts()?;
+
+            let length: Option<i32> = if matches.is_present("all") {
+                None
+            } else {
+                Some(length)
+            };
+
+            let mut transactions: AccountTransactions = match store {
+                Some(ref store) => {
+                    store.account_transactions(&accounts.items, length, start_date, end_date)?
+                }
+                None => bank_api.get_transactions_for_accounts(
+                    &accounts.items,
+                    length,
+                    start_date,
+                    end_date,
+                    |account, fetched, available| {
+                        eprintln!(
+                            "{}: fetched {} of {} transactions",
+                            account.name, fetched, available
+                        )
+                    },
+                )?,
+            };
+
+            transactions
+                .items
+                .retain(|row| filter.matches(&row.transaction));
+
+            if let Some(key) = matches.value_of("sort") {
+                let key: SortKey = key.parse()?;
+                sort(&mut transactions.items, key, matches.is_present("reverse"));
+            }
+
+            if let Some(by) = matches.value_of("group-by") {
+                let by: GroupBy = by.parse()?;
+                return print(&group(&transactions.items, by), output);
+            }
+
+            if let Some(format) = matches.value_of("format") {
+                let format: ExportFormat = format.parse()?;
+                let columns = parse_columns(
+                    matches
+                        .value_of("columns")
+                        .unwrap_or(DEFAULT_ACCOUNT_COLUMNS),
+                )?;
+
+                write_account_transactions(io::stdout(), &transactions.items, &columns, format)?;
+            } else {
+                print_items(&transactions, output)?;
+            }
+
+            return Ok(());
         }
 
         let account: String;
 
         if matches.is_present("interactive") {
-            let mut response: Accounts = bank_api.get_accounts()?;
+            let mut response: Accounts = get_accounts()?;
 
             let accounts: &mut Vec<AccountObj> = &mut response.items;
 
-            account = fuzzy_match_account(&accounts, "Select from_account")?
+            account = fuzzy_match_account(&accounts, "Select from_account", picker)?
                 .account_id
                 .to_string();
         } else {
-            account = match matches.value_of("account") {
-                Some(account) => account.to_string(),
+            account = match matches
+                .value_of("account")
+                .or_else(|| default_account.map(|a| a.as_str()))
+            {
+                Some(account) => {
+                    let accounts: Accounts = get_accounts()?;
+                    resolve_account(&accounts.items, aliases, account)?
+                        .account_id
+                        .to_string()
+                }
                 None => {
-                    return Err(Error::Parsable("account wasn't parsable"));
+                    return Err(Error::ArgumentMissing(
+                        "account, give --account or set default_account in your profile",
+                    ));
                 }
             };
         }
 
-        let transactions: Transactions =
-            bank_api.get_transactions(&account, length, start_date, end_date)?;
+        let mut transactions: Transactions = if let Some(ref store) = store {
+            let length: Option<i32> = if matches.is_present("all") {
+                None
+            } else {
+                Some(length)
+            };
+            store.transactions(&account, length, start_date, end_date)?
+        } else if matches.is_present("all") {
+            bank_api.get_all_transactions(
+                &account,
+                start_date,
+                end_date,
+                |fetched, available| eprintln!("Fetched {} of {} transactions", fetched, available),
+            )?
+        } else {
+            bank_api.get_transactions(&account, length, start_date, end_date)?
+        };
+
+        filter.apply(&mut transactions.items);
+
+        if let Some(key) = matches.value_of("sort") {
+            let key: SortKey = key.parse()?;
+            sort(&mut transactions.items, key, matches.is_present("reverse"));
+        }
+
+        if let Some(by) = matches.value_of("group-by") {
+            let by: GroupBy = by.parse()?;
+            return print(&group(&transactions.items, by), output);
+        }
+
+        if let Some(format) = matches.value_of("format") {
+            let format: ExportFormat = format.parse()?;
+            let columns = parse_columns(matches.value_of("columns").unwrap_or(DEFAULT_COLUMNS))?;
+
+            write_transactions(io::stdout(), &transactions.items, &columns, format)?;
+        } else {
+            print_items(&transactions, output)?;
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("report") {
+        if let Some(matches) = matches.subcommand_matches("summary") {
+            let from: NaiveDate = NaiveDate::parse_from_str(
+                matches.value_of("from").unwrap_or_default(),
+                "%Y-%m-%d",
+            )?;
+            let to: NaiveDate = match matches.value_of("to") {
+                Some(to) => NaiveDate::parse_from_str(to, "%Y-%m-%d")?,
+                None => Utc::now().naive_utc().date(),
+            };
+
+            if to < from {
+                return Err(Error::Parsable("end_date was earlier than start date"));
+            }
+
+            let start_date: DateTime<Utc> = Utc.from_utc_date(&from).and_hms(0, 0, 0);
+            let end_date: DateTime<Utc> = Utc.from_utc_date(&to).and_hms(23, 59, 59);
+
+            let accounts: Accounts = bank_api.get_accounts()?;
+
+            // Every page is fetched, a month with more transactions than
+            // one page would otherwise come out short.
+            let summary = if matches.is_present("all-accounts") {
+                let mut transactions = bank_api.get_transactions_for_accounts(
+                    &accounts.items,
+                    None,
+                    start_date,
+                    end_date,
+                    |account, fetched, available| {
+                        eprintln!(
+                            "{}: fetched {} of {} transactions",
+                            account.name, fetched, available
+                        )
+                    },
+                )?;
+
+                let internal_transfers = remove_internal_transfers(&mut transactions.items);
+                let mut summary = summarize(&transactions.items, from, to);
+                summary.internal_transfers = internal_transfers;
+                summary
+            } else {
+                let account = match matches
+                    .value_of("account")
+                    .or_else(|| default_account.map(|a| a.as_str()))
+                {
+                    Some(account) => resolve_account(&accounts.items, aliases, account)?,
+                    None => {
+                        return Err(Error::ArgumentMissing(
+                            "account, give --account or set default_account in your profile",
+                        ));
+                    }
+                };
+
+                let transactions = bank_api.get_all_transactions(
+                    &account.account_id,
+                    start_date,
+                    end_date,
+                    |fetched, available| {
+                        eprintln!("Fetched {} of {} transactions", fetched, available)
+                    },
+                )?;
+                summarize(&transactions.items, from, to)
+            };
+
+            match matches.value_of("format") {
+                Some(format) => write_summary(io::stdout(), &summary, format.parse()?)?,
+                None => print(&summary, output)?,
+            }
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("template") {
+        let mut templates = Templates::load()?;
+
+        if matches.subcommand_matches("list").is_some() {
+            for (name, template) in &templates.templates {
+                println!("{}\t{}", name, template);
+            }
+        }
+
+        if let Some(matches) = matches.subcommand_matches("add") {
+            let template = Template {
+                from: matches.value_of("from").unwrap_or_default().to_string(),
+                to: matches.value_of("to").unwrap_or_default().to_string(),
+                amount: parse_amount(matches.value_of("amount").unwrap_or_default())?,
+                message: matches.value_of("message").unwrap_or_default().to_string(),
+            };
+
+            // Catch typos now rather than when the template is first run.
+            template.validate()?;
+            let accounts: Accounts = bank_api.get_accounts()?;
+            let from = resolve_account(&accounts.items, aliases, &template.from)?;
+            let to = resolve_account(&accounts.items, aliases, &template.to)?;
+            if from.account_id == to.account_id {
+                return Err(Error::InvalidTransfer(ValidationError::SameAccount));
+            }
+
+            let name = matches.value_of("name").unwrap_or_default();
+            templates.templates.insert(name.to_string(), template);
+            templates.save()?;
+        }
+
+        if let Some(matches) = matches.subcommand_matches("remove") {
+            let name = matches.value_of("name").unwrap_or_default();
+            if templates.templates.remove(name).is_none() {
+                return Err(Error::UnknownTemplate(name.to_string()));
+            }
+            templates.save()?;
+        }
+    }
 
-        println!("{:}", transactions);
+    if matches.subcommand_matches("tui").is_some() {
+        Dashboard::new(bank_api)?.run()?;
     }
 
     if let Some(matches) = matches.subcommand_matches("transfer") {
+        if let Some(path) = matches.value_of("batch") {
+            let accounts: Accounts = bank_api.get_accounts()?;
+            let rows = batch::read_rows(path)?;
+
+            let mut transfers: Vec<PreparedTransfer> = Vec::new();
+            let mut invalid: usize = 0;
+            for (i, transfer) in batch::prepare(&rows, &accounts.items, aliases)
+                .into_iter()
+                .enumerate()
+            {
+                match transfer {
+                    Ok(transfer) => transfers.push(transfer),
+                    Err(err) => {
+                        eprintln!("Row {}: {}", i + 1, err);
+                        invalid += 1;
+                    }
+                }
+            }
+
+            if invalid > 0 {
+                return Err(Error::InvalidTransfer(ValidationError::InvalidBatchRows(
+                    invalid,
+                )));
+            }
+
+            if matches.is_present("dry-run") {
+                return print_list(&transfers, output);
+            }
+
+            if !matches.is_present("yes") {
+                for transfer in &transfers {
+                    eprintln!("{}\n", transfer);
+                }
+                if !confirm(&format!("Make these {} transfers?", transfers.len()))? {
+                    return Err(Error::Message("the transfers were cancelled"));
+                }
+            }
+
+            let report = batch::execute(bank_api, transfers);
+
+            print(&report, output)?;
+
+            if report.failed > 0 {
+                return Err(Error::TransferRejected {
+                    error_type: None,
+                    message: Some(format!(
+                        "{} of {} transfers failed",
+                        report.failed,
+                        report.succeeded + report.failed
+                    )),
+                    trace_id: None,
+                });
+            }
+
+            return Ok(());
+        }
+
         let from_account_id: String;
         let to_account_id: String;
-        let amount: f32;
+        let amount: Decimal;
         let message: String;
 
+        let accounts: Accounts = bank_api.get_accounts()?;
+
         if matches.is_present("interactive") {
-            let mut response: Accounts = bank_api.get_accounts()?;
+            let templates = Templates::load()?;
+
+            // Saved templates are offered first, followed by the accounts.
+            let mut options: Vec<String> = templates
+                .templates
+                .iter()
+                .map(|(name, template)| format!("{}: {}", name, template))
+                .collect();
+            options.extend(accounts.items.iter().map(account_option));
+
+            let index = match fuzzy_select(&options, "Select template or from_account", picker)? {
+                Some(index) => index,
+                None => return Err(Error::NoAccountFound("no account was selected")),
+            };
 
-            let accounts: &mut Vec<AccountObj> = &mut response.items;
+            if let Some(template) = templates.templates.values().nth(index) {
+                from_account_id = resolve_account(&accounts.items, aliases, &template.from)?
+                    .account_id
+                    .to_string();
+                to_account_id = resolve_account(&accounts.items, aliases, &template.to)?
+                    .account_id
+                    .to_string();
+                amount = template.amount;
+                message = template.message.to_string();
+            } else {
+                from_account_id = accounts.items[index - templates.templates.len()]
+                    .account_id
+                    .to_string();
+
+                let mut to_accounts: Vec<AccountObj> = accounts.items.clone();
+                remove_account(&mut to_accounts, &from_account_id);
+
+                to_account_id = fuzzy_match_account(&to_accounts, "Select to_account", picker)?
+                    .account_id
+                    .to_string();
+
+                println!("Amount: ");
+                let input: String = read!("{}\n");
+                amount = parse_amount(&input)?;
 
-            from_account_id = fuzzy_match_account(&accounts, "Select from_account")?
+                println!("Message: ");
+                message = read!("{}\n");
+            }
+        } else if let Some(name) = matches.value_of("template") {
+            let templates = Templates::load()?;
+            let template = templates.get(name)?;
+
+            from_account_id = resolve_account(&accounts.items, aliases, &template.from)?
                 .account_id
                 .to_string();
 
-            remove_account(accounts, &from_account_id);
-
-            to_account_id = fuzzy_match_account(&accounts, "Select to_account")?
+            to_account_id = resolve_account(&accounts.items, aliases, &template.to)?
                 .account_id
                 .to_string();
 
-            println!("Amount: ");
-            amount = read!("{}\n");
+            amount = match matches.value_of("amount") {
+                Some(amount) => parse_amount(amount)?,
+                None => template.amount,
+            };
 
-            println!("Message: ");
-            message = read!("{}\n");
+            message = template.message.to_string();
         } else {
             from_account_id = match matches.value_of("from") {
-                Some(value) => value.to_string(),
+                Some(value) => resolve_account(&accounts.items, aliases, value)?
+                    .account_id
+                    .to_string(),
                 None => return Err(Error::ArgumentMissing("from")),
             };
 
             to_account_id = match matches.value_of("to") {
-                Some(value) => value.to_string(),
+                Some(value) => resolve_account(&accounts.items, aliases, value)?
+                    .account_id
+                    .to_string(),
                 None => return Err(Error::ArgumentMissing("to")),
             };
 
             amount = match matches.value_of("amount") {
-                Some(amount) => match amount.parse::<f32>() {
-                    Ok(amount) => amount,
-                    Err(_) => {
-                        return Err(Error::Parsable(
-                            "amount couldn't be parsed to a float value",
-                        ));
-                    }
-                },
+                Some(amount) => parse_amount(amount)?,
                 None => return Err(Error::ArgumentMissing("amount")),
             };
 
@@ -250,13 +896,26 @@ fn main() -> Result<(), Error> {
             amount,
         };
 
-        let transfer = bank_api.post_transfer(&transfer)?;
+        validate(&accounts.items, &transfer)?;
+
+        let summary = TransferSummary::new(&accounts.items, &transfer)?;
 
-        if transfer.is_error {
-            return Err(Error::Message("couldn't perform your transfer"));
+        if matches.is_present("dry-run") {
+            return print(&summary, output);
         }
 
-        if color {
+        if !matches.is_present("yes") {
+            eprintln!("{}", summary);
+            if !confirm("Make this transfer?")? {
+                return Err(Error::Message("the transfer was cancelled"));
+            }
+        }
+
+        let transfer = bank_api.post_transfer(&transfer)?;
+
+        if output != OutputFormat::Text {
+            print_json(&transfer, output)?;
+        } else if color {
             println!(
                 "{}Your transfer was successfully executed.{}",
                 color::Fg(color::Green),
@@ -269,3 +928,48 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+/// What `auth test` checked. A failed step is reported as an error instead.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthReport {
+    steps: Vec<AuthStep>,
+    token_type: String,
+    expires_at: String,
+    expires_in: i32,
+    scope: String,
+    customer_name: String,
+}
+
+#[derive(Serialize)]
+struct AuthStep {
+    step: &'static str,
+    status: &'static str,
+}
+
+impl AuthStep {
+    fn ok(step: &'static str) -> AuthStep {
+        AuthStep { step, status: "ok" }
+    }
+}
+
+impl fmt::Display for AuthReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Client id and secret were accepted.")?;
+        writeln!(f, "Token type:\t{}", self.token_type)?;
+        writeln!(
+            f,
+            "Expires:\t{} (in {} seconds)",
+            self.expires_at, self.expires_in
+        )?;
+        writeln!(f, "Scope:\t\t{}", self.scope)?;
+        write!(f, "Customer id was accepted for {}.", self.customer_name)
+    }
+}
+
+fn is_client_error(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => status.is_client_error(),
+        None => false,
+    }
+}